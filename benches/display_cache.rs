@@ -0,0 +1,36 @@
+//! Times displaying a 10,000-digit integer over and over, with `display`, which caches the strings of very long numbers, and with plain formatting.
+//!
+//! Run with `cargo bench`. Only the first `display` formats the number, so the rest should be much faster than formatting it each time.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use math_lang::value::Val;
+use num::BigInt;
+
+/// How many times the number is displayed.
+const DISPLAYS: u32 = 200;
+
+/// Returns the average time of one call to `display`.
+fn time(mut display: impl FnMut() -> String) -> Duration {
+    let start = Instant::now();
+
+    for _ in 0..DISPLAYS {
+        black_box(display());
+    }
+
+    start.elapsed() / DISPLAYS
+}
+
+fn main() {
+    let digits = "1234567890".repeat(1_000);
+    let value: Box<dyn Val> = Box::new(BigInt::parse_bytes(digits.as_bytes(), 10).unwrap());
+
+    assert_eq!(value.display(), digits);
+
+    let formatted = time(|| format!("{value}"));
+    let displayed = time(|| value.display());
+
+    println!("formatted: {formatted:?} per display");
+    println!("cached:    {displayed:?} per display");
+}
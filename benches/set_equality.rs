@@ -0,0 +1,55 @@
+//! Times `==` between two large finite sets, which first rejects sets with different saved hashes, against comparing all their elements.
+//!
+//! Run with `cargo bench`. Distinct sets are rejected by their hashes alone, so the fast reject should be much faster for them.
+//! Equal sets need every element checked either way, so both should take about as long.
+
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use math_lang::set::FiniteSet;
+use math_lang::value::{Tuple, Val};
+use num::BigInt;
+
+/// How many times each comparison is repeated.
+const COMPARISONS: u32 = 200;
+
+/// Builds the set of 10,000 tuples `[a, b, c, d]` with each entry from 0 to 9, except that `[0, 0, 0, 0]` is swapped for `last`.
+fn tuples(last: i64) -> FiniteSet {
+    let mut elements = (0..10_000i64)
+        .map(|n| Box::new(Tuple(
+            [n / 1000, n / 100 % 10, n / 10 % 10, n % 10].into_iter().map(|digit| Box::new(BigInt::from(digit)) as Box<dyn Val>).collect()
+        )) as Box<dyn Val>)
+        .collect::<HashSet<_>>();
+
+    elements.remove(&(Box::new(Tuple(vec![Box::new(BigInt::from(0)); 4])) as Box<dyn Val>));
+    elements.insert(Box::new(Tuple(
+        [0, 0, 0, last].into_iter().map(|digit| Box::new(BigInt::from(digit)) as Box<dyn Val>).collect()
+    )));
+
+    FiniteSet::new(elements)
+}
+
+/// Returns the average time of one comparison.
+fn time(mut compare: impl FnMut() -> bool) -> Duration {
+    let start = Instant::now();
+
+    for _ in 0..COMPARISONS {
+        black_box(compare());
+    }
+
+    start.elapsed() / COMPARISONS
+}
+
+fn main() {
+    let a = tuples(0);
+    let b = tuples(10);
+    let c = tuples(0);
+
+    assert!(a != b && a == c);
+
+    println!("distinct sets, fast reject:     {:?} per comparison", time(|| black_box(&a) == black_box(&b)));
+    println!("distinct sets, every element:   {:?} per comparison", time(|| black_box(&a).has_same_elements(black_box(&b))));
+    println!("equal sets, fast reject:        {:?} per comparison", time(|| black_box(&a) == black_box(&c)));
+    println!("equal sets, every element:      {:?} per comparison", time(|| black_box(&a).has_same_elements(black_box(&c))));
+}
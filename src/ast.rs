@@ -1,6 +1,8 @@
-use crate::{token::Token, value::Val};
+use std::fmt;
 
-#[derive(Debug)]
+use crate::{token::{Token, TokenKind}, value::Val};
+
+#[derive(Debug, Default)]
 pub struct Ast {
     stmts: Vec<Box<dyn Stmt>>
 }
@@ -21,6 +23,36 @@ impl Ast {
     }
 }
 
+/// Reconstructs the source of the program, one statement per line.
+impl fmt::Display for Ast {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for stmt in &self.stmts {
+            writeln!(f, "{stmt}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a string in quotes, escaping what the lexer would otherwise read differently, so that it lexes back into the same string.
+fn fmt_string(string: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "\"")?;
+
+    for ch in string.chars() {
+        match ch {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '\r' => write!(f, "\\r")?,
+            '\0' => write!(f, "\\0")?,
+            ch => write!(f, "{ch}")?
+        }
+    }
+
+    write!(f, "\"")
+}
+
 macro_rules! create_structs {
     (
         impl $trait:ident for $( 
@@ -48,11 +80,12 @@ macro_rules! create_structs {
 
 pub mod stmt {
     use std::any::Any;
+    use std::fmt;
     use std::fmt::Debug;
 
     use super::expr::Expr;
 
-    pub trait Stmt : Any + Debug {
+    pub trait Stmt : Any + Debug + CloneStmt {
         fn as_any(&self) -> &dyn Any;
         fn as_any_mut(&mut self) -> &mut dyn Any;
     }
@@ -67,9 +100,59 @@ pub mod stmt {
         }
     }
 
+    pub trait CloneStmt {
+        fn clone_stmt(&self) -> Box<dyn Stmt>;
+    }
+
+    impl<T> CloneStmt for T
+    where 
+        T: 'static + Stmt + Clone
+    {
+        fn clone_stmt(&self) -> Box<dyn Stmt> {
+            Box::new(self.clone())
+        }
+    }
+
+    impl Clone for Box<dyn Stmt> {
+        fn clone(&self) -> Self {
+            self.clone_stmt()
+        }
+    }
+
+    impl fmt::Display for dyn Stmt {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            if let Some(ExprStmt(expr, is_to_log)) = self.downcast_ref() {
+                write!(f, "{expr}{}", if *is_to_log { "" } else { ";" })
+            } else if let Some(Block(stmts)) = self.downcast_ref() {
+                fmt_block(stmts, f)
+            } else if let Some(Import(path, alias)) = self.downcast_ref() {
+                write!(f, "import ")?;
+                super::fmt_string(path, f)?;
+                write!(f, " as {alias}")
+            } else {
+                write!(f, "{self:?}")
+            }
+        }
+    }
+
+    /// Writes the statements of a `do ... end` block, each indented on its own line.
+    pub(super) fn fmt_block(stmts: &[Box<dyn Stmt>], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "do")?;
+
+        for stmt in stmts {
+            for line in stmt.to_string().lines() {
+                writeln!(f, "    {line}")?;
+            }
+        }
+
+        write!(f, "end")
+    }
+
     create_structs!(
         impl Stmt for
-            ExprStmt(Box<dyn Expr>, bool) // bool is whether or not to log the resultant value.
+            ExprStmt(Box<dyn Expr>, bool), // bool is whether or not to log the resultant value.
+            Block(Vec<Box<dyn Stmt>>), // statements run in their own scope (do ... end)
+            Import(String, String) // the path of the file, and the name its symbols are under (import "path" as name)
     );
 }
 
@@ -80,8 +163,11 @@ pub mod expr {
     use std::fmt;
     use std::fmt::Debug;
 
-    use super::Val;
-    use super::Token;
+    use num::{BigInt, BigRational, Complex, Signed, Zero};
+
+    use super::{fmt_string, Val};
+    use super::{Token, TokenKind};
+    use super::stmt::Stmt;
 
     pub trait Expr : Any + Debug + CloneExpr {
         fn as_any(&self) -> &dyn Any;
@@ -101,15 +187,33 @@ pub mod expr {
     impl fmt::Display for dyn Expr {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             if let Some(Literal(val)) = self.downcast_ref() {
-                write!(f, "{}", val)
+                fmt_literal(val.as_ref(), f)
             } else if let Some(Symbol(name)) = self.downcast_ref() {
                 write!(f, "{}", name)
             } else if let Some(Group(expr)) = self.downcast_ref() {
                 write!(f, "({})", expr)
             } else if let Some(Unary(op, expr)) = self.downcast_ref() {
                 write!(f, "{}{}", op.lexeme(), expr)
+            } else if let Some(Postfix(expr, op)) = self.downcast_ref() {
+                write!(f, "{}{}", expr, op.lexeme())
+            } else if let Some(Index(expr, i)) = self.downcast_ref() {
+                write!(f, "{}[{}]", expr, i)
+            } else if let Some(Slice(expr, start, end)) = self.downcast_ref() {
+                write!(f, "{}[{}..{}]", expr, start, end)
             } else if let Some(Binary(left, op, right)) = self.downcast_ref() {
-                write!(f, "{} {} {}", left, op.lexeme(), right)
+                if is_member(self) {
+                    write!(f, "{}.{}", left, right)
+                } else {
+                    write!(f, "{} {} {}", left, op.lexeme(), right)
+                }
+            } else if let Some(Chain(first, links)) = self.downcast_ref() {
+                write!(f, "{}", first)?;
+
+                for (op, right) in links {
+                    write!(f, " {} {}", op.lexeme(), right)?;
+                }
+
+                Ok(())
             } else if let Some(Call(caller, args)) = self.downcast_ref() {
                 write!(f, "{}(", caller)?;
 
@@ -126,7 +230,37 @@ pub mod expr {
                 }
 
                 write!(f, ")")
-            } else if let Some(Func(args, expr)) = self.downcast_ref() {
+            } else if let Some(Assign(Symbol(name), value)) = self.downcast_ref() {
+                // Functions are written how they are defined, as `f(x) = ...`
+                if let Some(Func(args, expr, arg_types)) = value.downcast_ref() {
+                    write!(f, "{name}(")?;
+
+                    for (i, (arg, typeset)) in args.iter().zip(arg_types).enumerate() {
+                        write!(f, "{}{}", if i == 0 { "" } else { ", " }, arg.0)?;
+
+                        if let Some(typeset) = typeset {
+                            write!(f, " : {typeset}")?;
+                        }
+                    }
+
+                    write!(f, ") = {expr}")
+                } else {
+                    write!(f, "{name} = {value}")
+                }
+            } else if let Some(TypedAssign(Symbol(name), typeset, value)) = self.downcast_ref() {
+                write!(f, "{name} : {typeset} = {value}")
+            } else if let Some(TypeExpr(value, typeset)) = self.downcast_ref() {
+                write!(f, "{value} : {typeset}")
+            } else if let Some(FuncTypeExpr(func, arg_types, codomain)) = self.downcast_ref() {
+                write!(f, "{func} : ")?;
+
+                for (i, typeset) in arg_types.iter().enumerate() {
+                    write!(f, "{}{typeset}", if i == 0 { "" } else { ", " })?;
+                }
+
+                write!(f, " -> {codomain}")
+            } else if let Some(Func(args, expr, _)) = self.downcast_ref() {
+                // The parser only makes functions as the value of an `Assign`, which writes them as `f(x) = ...`, so this is just for debugging
                 if args.len() == 1 {
                     write!(f, "{} -> ", args[0].0)
                 } else {
@@ -134,7 +268,7 @@ pub mod expr {
         
                     for (i, a) in args.iter().enumerate() {
                         if i == args.len() - 1 {
-                            s.push_str(&format!("{}", a.0));
+                            s.push_str(&a.0.to_string());
                         } else {
                             s.push_str(&format!("{}, ", a.0));
                         }
@@ -149,8 +283,8 @@ pub mod expr {
             } else if let Some(Tuple(exprs)) = self.downcast_ref() {
                 write!(f, "[")?;
 
-                for expr in exprs {
-                    write!(f, "{}", expr)?;
+                for (i, expr) in exprs.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 { "" } else { ", " }, expr)?;
                 }
 
                 write!(f, "]")
@@ -175,14 +309,92 @@ pub mod expr {
             } else if let Some(Set(exprs)) = self.downcast_ref() {
                 write!(f, "{{")?;
 
-                for expr in exprs {
-                    write!(f, "{}", expr)?;
+                for (i, expr) in exprs.iter().enumerate() {
+                    write!(f, "{}{}", if i == 0 { "" } else { ", " }, expr)?;
                 }
 
                 write!(f, "}}")
+            } else if let Some(SetBuilder(Symbol(name), source, cond)) = self.downcast_ref() {
+                write!(f, "{{{name} : {name} <: {source}")?;
+
+                if let Some(cond) = cond {
+                    write!(f, " & {cond}")?;
+                }
+
+                write!(f, "}}")
+            } else if let Some(Comprehension(output, binders, cond)) = self.downcast_ref() {
+                write!(f, "{{{output} | ")?;
+
+                for (i, (Symbol(name), source)) in binders.iter().enumerate() {
+                    write!(f, "{}{name} : {source}", if i == 0 { "" } else { ", " })?;
+                }
+
+                if let Some(cond) = cond {
+                    write!(f, " & {cond}")?;
+                }
+
+                write!(f, "}}")
+            } else if let Some(BlockExpr(stmts)) = self.downcast_ref() {
+                super::stmt::fmt_block(stmts, f)
+            } else {
+                write!(f, "{self:?}")
+            }
+        }
+    }
+
+    /// Checks if the expression is a dotted name like `L.name` (or `A.B.name`), which is written without spaces.
+    pub fn is_member(expr: &dyn Expr) -> bool {
+        match expr.downcast_ref() {
+            Some(Binary(left, op, right)) => op.kind() == &TokenKind::Dot
+                && right.downcast_ref::<Symbol>().is_some()
+                && (left.downcast_ref::<Symbol>().is_some() || is_member(left.as_ref())),
+            _ => false
+        }
+    }
+
+    /// Writes a literal so that it parses back into the same value: strings are quoted, and numbers that aren't a single number literal are put in parentheses.
+    fn fmt_literal(val: &dyn Val, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(string) = val.downcast_ref::<String>() {
+            fmt_string(string, f)
+        } else if let Some(bigint) = val.downcast_ref::<BigInt>() {
+            if bigint.is_negative() {
+                write!(f, "({bigint})")
+            } else {
+                write!(f, "{bigint}")
+            }
+        } else if let Some(bigrat) = val.downcast_ref::<BigRational>() {
+            if bigrat.is_integer() && !bigrat.is_negative() {
+                write!(f, "{}.0", bigrat.numer())
+            } else {
+                write!(f, "({})", real_source(bigrat))
+            }
+        } else if let Some(complex) = val.downcast_ref::<Complex<BigRational>>() {
+            // Imaginary literals are written like `3i` or `5i / 2`
+            let im = if complex.im.is_integer() {
+                format!("{}i", complex.im.numer().abs())
+            } else {
+                format!("{}i / {}", complex.im.numer().abs(), complex.im.denom())
+            };
+            let sign = if complex.im.is_negative() { "-" } else { "+" };
+
+            if complex.re.is_zero() && complex.im.is_integer() && !complex.im.is_negative() {
+                write!(f, "{im}")
+            } else if complex.re.is_zero() {
+                write!(f, "({}{im})", if complex.im.is_negative() { "-" } else { "" })
             } else {
-                todo!()
+                write!(f, "({} {sign} {im})", real_source(&complex.re))
             }
+        } else {
+            write!(f, "{val}")
+        }
+    }
+
+    /// Writes a real number as source that gives back a real, like `2.0` or `-5 / 2`.
+    fn real_source(real: &BigRational) -> String {
+        if real.is_integer() {
+            format!("{}.0", real.numer())
+        } else {
+            format!("{} / {}", real.numer(), real.denom())
         }
     }
 
@@ -211,15 +423,91 @@ pub mod expr {
             Symbol(String),
             Group(Box<dyn Expr>),
             Unary(Token, Box<dyn Expr>),
+            Postfix(Box<dyn Expr>, Token), // operand, then operator (5!)
+            Index(Box<dyn Expr>, Box<dyn Expr>), // tuple or string, then the index of the element (t[0], t.0)
+            Slice(Box<dyn Expr>, Box<dyn Expr>, Box<dyn Expr>), // tuple or string, then the start and end of the range (t[1..3])
             Binary(Box<dyn Expr>, Token, Box<dyn Expr>),
+            Chain(Box<dyn Expr>, Vec<(Token, Box<dyn Expr>)>), // first operand, then each comparison after it (0 <= x < 10)
             Call(Box<dyn Expr>, Vec<Option<Box<dyn Expr>>>),
             Assign(Symbol, Box<dyn Expr>),
             TypedAssign(Symbol, Box<dyn Expr>, Box<dyn Expr>), // name, type, value (x : Int = 5; y : {1, 2, 3} = 0)
-            Func(Vec<Symbol>, Box<dyn Expr>),
+            Func(Vec<Symbol>, Box<dyn Expr>, Vec<Option<Box<dyn Expr>>>), // args, body, and the type of each arg if given inline (f(x : Nat) = x + 1)
             Tuple(Vec<Box<dyn Expr>>),
             Matrix(Vec<Vec<Box<dyn Expr>>>),
             Set(Vec<Box<dyn Expr>>), // store exprs in a vector, and turn into set when resolving values
+            SetBuilder(Symbol, Box<dyn Expr>, Option<Box<dyn Expr>>), // bound variable, source set, condition ({ x : x <: Nat & x < 10 })
+            Comprehension(Box<dyn Expr>, Vec<(Symbol, Box<dyn Expr>)>, Option<Box<dyn Expr>>), // output, each bound variable and its source set, condition ({ [x, y] | x : A, y : B & x < y })
             TypeExpr(Box<dyn Expr>, Box<dyn Expr>), // value, type (2 : Int; msg : Str)
-            FuncTypeExpr(Box<dyn Expr>, Vec<Box<dyn Expr>>, Box<dyn Expr>) // value, arg types, outtype
+            FuncTypeExpr(Box<dyn Expr>, Vec<Box<dyn Expr>>, Box<dyn Expr>), // value, arg types, outtype
+            BlockExpr(Vec<Box<dyn Stmt>>) // a `do ... end` block used as a value, which is its last statement's (x = do y = 2; y + 1 end)
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{expr, stmt};
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn reconstruct(source: &str) -> String {
+        let tokens = Lexer::new(source.as_bytes()).lex().unwrap();
+
+        Parser::new(&tokens).parse().unwrap().to_string()
+    }
+
+    #[test]
+    fn reconstructed_source_parses_to_the_same_program() {
+        let source = "\
+x = 1
+y : Int = -2
+z : Nat
+f(a : Nat, b) = a ^ 2 + b / 2.5 - 3i
+g(t) = t[0] + t[1..2] + #{1, 2} + 5!
+h : Int, Real -> Real
+S = {n : n <: Nat & n < 10}
+T = {[p, q] | p : S, q : S & p < q}
+0 <= x < 10;
+(x + 1) * 2 : Int
+[1, 2; 3, 4]
+\"text\" == \"text\" && !true || false
+s = \"q\\\"x\\ty\" + r\"a\\nb\" + \"c\\\\d\\n\"
+do
+  w = 3
+  w
+end
+v = do
+  u = 1;
+  u + 1
+end
+{1} | {2} & Nat \\ {3} ~ Real
+y <=: Nat
+";
+        let reconstructed = reconstruct(source);
+
+        assert_eq!(reconstructed.lines().count(), source.lines().count());
+        assert_eq!(reconstruct(&reconstructed), reconstructed);
+    }
+
+    #[test]
+    fn reconstructed_strings_keep_their_characters() {
+        for (source, expected) in [
+            (r#""q\"x\ty""#, "q\"x\ty"),
+            (r#"r"a\nb""#, "a\\nb"),
+            (r#""a\nb\\""#, "a\nb\\"),
+            ("\"\u{1}\"", "\u{1}")
+        ] {
+            let reconstructed = reconstruct(source);
+            let tokens = Lexer::new(reconstructed.as_bytes()).lex().unwrap();
+            let ast = Parser::new(&tokens).parse().unwrap();
+
+            let Some(stmt::ExprStmt(expr, _)) = ast.stmts()[0].downcast_ref() else {
+                panic!("expected an expression")
+            };
+            let Some(expr::Literal(value)) = expr.downcast_ref() else {
+                panic!("expected '{reconstructed}' to be a literal")
+            };
+
+            assert_eq!(value.downcast_ref::<String>().map(String::as_str), Some(expected), "reconstructing {source}");
+        }
+    }
+}
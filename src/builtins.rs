@@ -0,0 +1,736 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use num::bigint::Sign;
+use num::{BigInt, BigRational, Complex, Integer, Signed, ToPrimitive, Zero};
+use num::pow::Pow;
+
+use crate::environment::SymStore;
+use crate::error::{self, Error, RuntimeError};
+use crate::set::{canon, CanonSet, InfiniteSet, PredicateSet, Set};
+use crate::value::{Func, NativeFunc, NumKind, NumVal, Tuple, Unit, Val};
+
+/// Returns the function's argument types, as a tuple of sets.
+pub fn domain(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let func = expect_func(args[0].as_ref(), "domain")?;
+    let env = func.env().borrow();
+
+    Ok(Box::new(Tuple(func
+        .args()
+        .iter()
+        .map(|arg_name| if let Some(SymStore::Type(typeset)) = env.get(arg_name) {
+            Box::new(typeset) as Box<dyn Val>
+        } else {
+            unreachable!()
+        })
+        .collect())))
+}
+
+/// Returns the set the function maps into.
+pub fn codomain(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    Ok(Box::new(Rc::clone(expect_func(args[0].as_ref(), "codomain")?.codomain())))
+}
+
+/// Returns whether the two sets have no elements in common.
+pub fn disjoint(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let a = expect_set(args[0].as_ref(), "disjoint")?;
+    let b = expect_set(args[1].as_ref(), "disjoint")?;
+
+    match a.disjoint(b) {
+        Some(disjoint) => Ok(Box::new(disjoint)),
+        None => error(format!("Cannot decide whether '{a}' and '{b}' are disjoint"))
+    }
+}
+
+/// Returns the set of values the given unary function maps to `true`.
+pub fn setof(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let pred = expect_func(args[0].as_ref(), "setof")?;
+
+    if pred.arity() != 1 {
+        return error(format!("'setof' expects a function of one argument, but got '{pred}'"))
+    }
+
+    Ok(Box::new(Rc::new(CanonSet::Predicate(PredicateSet::new(pred.to_owned())))))
+}
+
+/// Returns the smallest builtin type containing the value, going by value rather than representation, so `typeof(6/2)` is `Nat`.
+/// 
+/// It is registered as `typeof`, which is a reserved word in Rust.
+pub fn type_of(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let typeset = [InfiniteSet::Nat, InfiniteSet::Int, InfiniteSet::Real, InfiniteSet::Complex, InfiniteSet::Str]
+        .into_iter()
+        .find(|typeset| typeset.contains(args[0].as_ref()))
+        .unwrap_or(InfiniteSet::Univ);
+
+    Ok(Box::new(Rc::new(CanonSet::Infinite(typeset))))
+}
+
+/// Returns the elements of a finite set as a tuple, in the order the set is displayed.
+pub fn elements(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    match expect_set(args[0].as_ref(), "elements")?.as_ref() {
+        CanonSet::Finite(set) => Ok(Box::new(Tuple(set.sorted().into_iter().map(|element| element.clone_box()).collect()))),
+        set => error(format!("'elements' expects a finite set, but got '{set}'"))
+    }
+}
+
+/// Returns the union of every set in a finite set of sets, so `flatten({{1, 2}, {2, 3}})` is `{1, 2, 3}`.
+pub fn flatten(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let union = expect_set_of_sets(args[0].as_ref(), "flatten")?
+        .into_iter()
+        .reduce(|union, set| canon(Rc::new(CanonSet::Union(union, set))))
+        .unwrap_or_else(|| Rc::new(CanonSet::empty()));
+
+    Ok(Box::new(union))
+}
+
+/// Returns the intersection of every set in a finite, non-empty set of sets, so `intersect_all({{1, 2, 3}, {2, 3, 4}})` is `{2, 3}`.
+/// 
+/// An empty collection is an error, rather than `Univ`, as that is rarely what is meant.
+pub fn intersect_all(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let intersection = expect_set_of_sets(args[0].as_ref(), "intersect_all")?
+        .into_iter()
+        .reduce(|intersection, set| canon(Rc::new(CanonSet::Intersect(intersection, set))));
+
+    match intersection {
+        Some(intersection) => Ok(Box::new(intersection)),
+        None => error("'intersect_all' expects at least one set")
+    }
+}
+
+/// Returns a tuple of `n` copies of the value.
+pub fn replicate(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let Some(n) = expect_int(args[1].as_ref(), "replicate")?.to_usize() else {
+        return error(format!("'replicate' expects a natural number of copies, but got '{}'", args[1]))
+    };
+
+    Ok(Box::new(Tuple(vec![args[0].clone(); n])))
+}
+
+/// Pairs each element of the tuple with its index, as `[index, element]`, starting from 0.
+pub fn indexed(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let Tuple(elements) = expect_tuple(args[0].as_ref(), "indexed")?;
+
+    Ok(Box::new(Tuple(elements
+        .iter()
+        .enumerate()
+        .map(|(i, element)| Box::new(Tuple(vec![Box::new(BigInt::from(i)), element.clone()])) as Box<dyn Val>)
+        .collect())))
+}
+
+/// Returns the tuple with its elements in order. Values of different types are ordered by their type, like in a set.
+pub fn sort(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let mut elements = expect_tuple(args[0].as_ref(), "sort")?.0.clone();
+
+    // The comparator can't fail, so any pair without an order is found first
+    for (i, l) in elements.iter().enumerate() {
+        for r in &elements[i + 1..] {
+            expect_order(l.as_ref(), r.as_ref(), "sort")?;
+        }
+    }
+
+    elements.sort_by(|l, r| l.cmp_val(r.as_ref()).unwrap_or(Ordering::Equal));
+
+    Ok(Box::new(Tuple(elements)))
+}
+
+/// Returns the smallest of the values, or the first of the smallest if several are equal.
+pub fn min(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let mut least = &args[0];
+
+    for arg in &args[1..] {
+        if expect_order(arg.as_ref(), least.as_ref(), "min")?.is_lt() {
+            least = arg;
+        }
+    }
+
+    Ok(least.clone())
+}
+
+/// Returns the largest of the values, or the first of the largest if several are equal.
+pub fn max(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let mut most = &args[0];
+
+    for arg in &args[1..] {
+        if expect_order(arg.as_ref(), most.as_ref(), "max")?.is_gt() {
+            most = arg;
+        }
+    }
+
+    Ok(most.clone())
+}
+
+/// Checks if the string begins with the prefix.
+pub fn starts_with(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    Ok(Box::new(expect_str(args[0].as_ref(), "starts_with")?.starts_with(expect_str(args[1].as_ref(), "starts_with")?)))
+}
+
+/// Checks if the string finishes with the suffix.
+pub fn ends_with(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    Ok(Box::new(expect_str(args[0].as_ref(), "ends_with")?.ends_with(expect_str(args[1].as_ref(), "ends_with")?)))
+}
+
+/// Checks if the substring appears anywhere in the string.
+pub fn contains(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    Ok(Box::new(expect_str(args[0].as_ref(), "contains")?.contains(expect_str(args[1].as_ref(), "contains")?)))
+}
+
+/// Returns the string in uppercase, where some characters may become several, like `ß` becoming `SS`.
+pub fn upper(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    Ok(Box::new(expect_str(args[0].as_ref(), "upper")?.to_uppercase()))
+}
+
+/// Returns the string in lowercase.
+pub fn lower(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    Ok(Box::new(expect_str(args[0].as_ref(), "lower")?.to_lowercase()))
+}
+
+/// Returns the string without any whitespace at its start or end.
+pub fn trim(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    Ok(Box::new(expect_str(args[0].as_ref(), "trim")?.trim().to_owned()))
+}
+
+/// Splits the string at each occurrence of the separator, into a tuple of the strings between them.
+pub fn split(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let sep = expect_str(args[1].as_ref(), "split")?;
+
+    if sep.is_empty() {
+        return error("'split' expects a non-empty separator")
+    }
+
+    Ok(Box::new(Tuple(expect_str(args[0].as_ref(), "split")?
+        .split(sep)
+        .map(|part| Box::new(part.to_owned()) as Box<dyn Val>)
+        .collect())))
+}
+
+/// Joins a tuple of strings into one string, with the separator between each of them.
+pub fn join(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let Tuple(elements) = expect_tuple(args[0].as_ref(), "join")?;
+    let sep = expect_str(args[1].as_ref(), "join")?;
+
+    Ok(Box::new(elements
+        .iter()
+        .map(|element| expect_str(element.as_ref(), "join"))
+        .collect::<error::Result<Vec<_>>>()?
+        .join(sep)))
+}
+
+/// Replaces each `{}` in the template with the next argument, as it is displayed. `{{` and `}}` are literal braces.
+pub fn format(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let template = expect_str(args[0].as_ref(), "format")?;
+    let mut values = args[1..].iter();
+    let mut placeholders = 0;
+    let mut result = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match (ch, chars.peek()) {
+            ('{', Some('}')) => {
+                chars.next();
+                placeholders += 1;
+
+                if let Some(value) = values.next() {
+                    result.push_str(&value.display());
+                }
+            }
+            ('{', Some('{')) | ('}', Some('}')) => {
+                chars.next();
+                result.push(ch);
+            }
+            _ => result.push(ch)
+        }
+    }
+
+    if placeholders != args.len() - 1 {
+        return error(format!("'format' has {placeholders} placeholder(s), but was given {} value(s)", args.len() - 1))
+    }
+
+    Ok(Box::new(result))
+}
+
+/// Does nothing if the condition holds, and fails otherwise.
+pub fn assert(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    match args[0].downcast_ref::<bool>() {
+        Some(true) => Ok(Box::new(Unit)),
+        Some(false) => error("Assertion failed: condition is 'false'"),
+        None => error(format!("'assert' expects a bool, but got '{}'", args[0]))
+    }
+}
+
+/// Does nothing if both values are equal, and fails with both of them otherwise.
+pub fn assert_eq(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    if args[0].compare(args[1].as_ref()) {
+        Ok(Box::new(Unit))
+    } else {
+        error(format!("Assertion failed: '{}' does not equal '{}'", args[0], args[1]))
+    }
+}
+
+/// Returns `-1`, `0`, or `1` for the sign of a real number.
+/// 
+/// Complex numbers with a nonzero imaginary part are an error, as their sign `x/|x|` usually isn't rational.
+pub fn sign(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    Ok(Box::new(BigInt::from(match expect_real(args[0].as_ref(), "sign")?.numer().sign() {
+        Sign::Minus => -1,
+        Sign::NoSign => 0,
+        Sign::Plus => 1
+    })))
+}
+
+/// Returns the absolute value of the number, keeping its type, so `abs(-2)` is still an Int.
+///
+/// Complex numbers with a nonzero imaginary part give their [`modulus`].
+pub fn abs(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    match args[0].as_number() {
+        Some(NumVal::Int(int)) => Ok(Box::new(int.abs())),
+        Some(NumVal::Real(real)) => Ok(Box::new(real.abs())),
+        Some(NumVal::Complex(z)) if z.im.is_zero() => Ok(Box::new(Complex::new(z.re.abs(), z.im))),
+        Some(NumVal::Complex(_)) => modulus(args),
+        None => error(format!("'abs' expects a number, but got '{}'", args[0]))
+    }
+}
+
+/// Returns the greatest common divisor of two integers, which is never negative, and `gcd(0, 0)` is `0`.
+pub fn gcd(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    Ok(Box::new(expect_int(args[0].as_ref(), "gcd")?.gcd(&expect_int(args[1].as_ref(), "gcd")?)))
+}
+
+/// Rounds the real number to `n` decimal places, with halves rounded away from zero, so it stays exact.
+pub fn round_to(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let real = expect_real(args[0].as_ref(), "round_to")?;
+    let Some(places) = expect_int(args[1].as_ref(), "round_to")?.to_u32() else {
+        return error(format!("'round_to' expects a non-negative number of decimal places, but got '{}'", args[1]))
+    };
+
+    let scale = BigRational::from(BigInt::from(10).pow(places));
+
+    Ok(Box::new((real * &scale).round() / scale))
+}
+
+/// Returns the complex number `re + im*i`, from its real and imaginary parts.
+pub fn complex(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    Ok(Box::new(Complex::new(expect_real(args[0].as_ref(), "complex")?, expect_real(args[1].as_ref(), "complex")?)))
+}
+
+/// Returns the distance of the number from `0`.
+///
+/// It is exact when the distance is rational (like `modulus(3 + 4i)`), and otherwise a rational approximation, unless results are being kept exact.
+pub fn modulus(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let z = expect_complex(args[0].as_ref(), "modulus")?;
+
+    if z.im.is_zero() {
+        return Ok(Box::new(z.re.abs()))
+    }
+
+    let squared = z.norm_sqr();
+
+    match exact_sqrt(&squared) {
+        Some(root) => Ok(Box::new(root)),
+        None => approximate(squared.to_f64().map(f64::sqrt), "modulus", args[0].as_ref())
+    }
+}
+
+/// Returns the square root of a real number, which is imaginary for negative numbers, so `sqrt(-4)` is `2i`.
+///
+/// Only perfect squares (like `9/4`) have a root for now, as any other root would have to be approximated.
+pub fn sqrt(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let real = expect_real(args[0].as_ref(), "sqrt")?;
+    let Some(root) = exact_sqrt(&real.abs()) else {
+        return error(format!("'sqrt' only takes the root of a perfect square for now, and '{}' is not one", args[0]))
+    };
+
+    if real.is_negative() {
+        Ok(Box::new(Complex::new(BigRational::zero(), root)))
+    } else if root.is_integer() {
+        Ok(Box::new(root.to_integer()))
+    } else {
+        Ok(Box::new(root))
+    }
+}
+
+/// Returns the angle of the number from the positive real axis, in radians, between `-pi` and `pi`.
+///
+/// It is exact for numbers on the real axis, and otherwise a rational approximation, unless results are being kept exact.
+pub fn arg(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let z = expect_complex(args[0].as_ref(), "arg")?;
+
+    if z.is_zero() {
+        return error("'arg' is undefined for '0'")
+    } else if z.im.is_zero() && z.re.is_positive() {
+        return Ok(Box::new(BigRational::zero()))
+    }
+
+    approximate(z.im.to_f64().zip(z.re.to_f64()).map(|(im, re)| im.atan2(re)), "arg", args[0].as_ref())
+}
+
+/// Returns the integer in hexadecimal, as a string.
+pub fn hex(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    Ok(Box::new(expect_int(args[0].as_ref(), "hex")?.to_str_radix(16)))
+}
+
+/// Returns the integer in binary, as a string.
+pub fn bin(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    Ok(Box::new(expect_int(args[0].as_ref(), "bin")?.to_str_radix(2)))
+}
+
+/// Returns the real number as a fraction `p/q`, as a string, even if it is an integer.
+pub fn frac(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+    let real = expect_real(args[0].as_ref(), "frac")?;
+
+    Ok(Box::new(format!("{}/{}", real.numer(), real.denom())))
+}
+
+fn error<T>(message: impl Into<String>) -> error::Result<T> {
+    Err(Error::Runtime(RuntimeError::new(message)))
+}
+
+fn expect_func<'v>(value: &'v dyn Val, builtin: &str) -> error::Result<&'v Func> {
+    if let Some(func) = value.downcast_ref::<Func>() {
+        Ok(func)
+    } else if let Some(native) = value.downcast_ref::<NativeFunc>() {
+        error(format!("'{builtin}' cannot inspect builtin function '{}'", native.name()))
+    } else {
+        error(format!("'{builtin}' expects a function, but got '{value}'"))
+    }
+}
+
+fn expect_set<'v>(value: &'v dyn Val, builtin: &str) -> error::Result<&'v Rc<CanonSet>> {
+    match value.downcast_ref::<Rc<CanonSet>>() {
+        Some(set) => Ok(set),
+        None => error(format!("'{builtin}' expects a set, but got '{value}'"))
+    }
+}
+
+/// Unboxes the elements of a finite set which must all be sets themselves, in the order the set is displayed.
+fn expect_set_of_sets(value: &dyn Val, builtin: &str) -> error::Result<Vec<Rc<CanonSet>>> {
+    let CanonSet::Finite(sets) = expect_set(value, builtin)?.as_ref() else {
+        return error(format!("'{builtin}' expects a finite set of sets, but got '{value}'"))
+    };
+
+    sets
+        .sorted()
+        .into_iter()
+        .map(|set| match set.downcast_ref::<Rc<CanonSet>>() {
+            Some(set) => Ok(Rc::clone(set)),
+            None => error(format!("'{builtin}' expects a set of sets, but '{set}' is not a set"))
+        })
+        .collect()
+}
+
+fn expect_tuple<'v>(value: &'v dyn Val, builtin: &str) -> error::Result<&'v Tuple> {
+    match value.downcast_ref::<Tuple>() {
+        Some(tuple) => Ok(tuple),
+        None => error(format!("'{builtin}' expects a tuple, but got '{value}'"))
+    }
+}
+
+fn expect_order(left: &dyn Val, right: &dyn Val, builtin: &str) -> error::Result<Ordering> {
+    match left.cmp_val(right) {
+        Some(ordering) => Ok(ordering),
+        None => error(format!("'{builtin}' cannot order '{left}' and '{right}'"))
+    }
+}
+
+fn expect_str<'v>(value: &'v dyn Val, builtin: &str) -> error::Result<&'v str> {
+    match value.downcast_ref::<String>() {
+        Some(string) => Ok(string),
+        None => error(format!("'{builtin}' expects a string, but got '{value}'"))
+    }
+}
+
+/// Returns the square root of a non-negative rational, if both its numerator and denominator are perfect squares.
+fn exact_sqrt(square: &BigRational) -> Option<BigRational> {
+    let (numer_root, denom_root) = (square.numer().sqrt(), square.denom().sqrt());
+
+    (&(&numer_root * &numer_root) == square.numer() && &(&denom_root * &denom_root) == square.denom())
+        .then(|| BigRational::new(numer_root, denom_root))
+}
+
+/// Unboxes a number that is an integer by value, whatever its representation.
+fn expect_int(value: &dyn Val, builtin: &str) -> error::Result<BigInt> {
+    match value.as_bigint() {
+        Some(int) => Ok(int),
+        None => error(format!("'{builtin}' expects an integer, but got '{value}'"))
+    }
+}
+
+/// Unboxes any number, as a complex number.
+fn expect_complex(value: &dyn Val, builtin: &str) -> error::Result<Complex<BigRational>> {
+    match value.as_number().map(|num| num.promote(NumKind::Complex)) {
+        Some(NumVal::Complex(complex)) => Ok(complex),
+        _ => error(format!("'{builtin}' expects a number, but got '{value}'"))
+    }
+}
+
+/// Converts the result of a floating point calculation on `value` back into a rational, which the interpreter only accepts when it isn't keeping results exact.
+fn approximate<T>(result: Option<f64>, builtin: &str, value: &dyn Val) -> error::Result<T> {
+    match result.and_then(BigRational::from_float) {
+        Some(approx) => Err(Error::Runtime(RuntimeError::Approximated(Box::new(approx)))),
+        None => error(format!("'{builtin}' cannot approximate its result for '{value}'"))
+    }
+}
+
+/// Unboxes a number that is real by value, whatever its representation.
+fn expect_real(value: &dyn Val, builtin: &str) -> error::Result<BigRational> {
+    match value.as_bigrational() {
+        Some(real) => Ok(real),
+        None => error(format!("'{builtin}' expects a real number, but got '{value}'"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::set::FiniteSet;
+
+    fn ints(values: &[i32]) -> Vec<Box<dyn Val>> {
+        values.iter().map(|&value| Box::new(BigInt::from(value)) as Box<dyn Val>).collect()
+    }
+
+    fn real(numer: i32, denom: i32) -> Box<dyn Val> {
+        Box::new(BigRational::new(BigInt::from(numer), BigInt::from(denom)))
+    }
+
+    fn string(value: &str) -> Box<dyn Val> {
+        Box::new(value.to_owned())
+    }
+
+    fn strings(values: &[&str]) -> Box<dyn Val> {
+        Box::new(Tuple(values.iter().map(|value| string(value)).collect()))
+    }
+
+    fn set(elements: Vec<Box<dyn Val>>) -> Box<dyn Val> {
+        Box::new(Rc::new(CanonSet::Finite(FiniteSet::new(elements.into_iter().collect()))))
+    }
+
+    #[test]
+    fn sort_orders_mixed_numbers() {
+        let half = Box::new(BigRational::new(BigInt::from(1), BigInt::from(2)));
+        let mut elements = ints(&[3, -1]);
+        elements.push(half);
+
+        let sorted = sort(&[Box::new(Tuple(elements))]).unwrap();
+
+        assert_eq!(sorted.to_string(), "[-1, 1/2, 3]");
+    }
+
+    #[test]
+    fn sort_without_an_order_is_an_error() {
+        // Complex numbers have no order, even with reals
+        let mut elements = ints(&[1]);
+        elements.push(Box::new(Complex::new(BigRational::zero(), BigRational::from(BigInt::from(1)))));
+
+        assert!(sort(&[Box::new(Tuple(elements))]).is_err());
+    }
+
+    #[test]
+    fn min_and_max() {
+        let args = ints(&[2, -5, 7, -5]);
+
+        assert!(min(&args).unwrap().compare(&BigInt::from(-5)));
+        assert!(max(&args).unwrap().compare(&BigInt::from(7)));
+    }
+
+    #[test]
+    fn gcd_is_never_negative() {
+        assert!(gcd(&ints(&[-4, 6])).unwrap().compare(&BigInt::from(2)));
+        assert!(gcd(&ints(&[0, 0])).unwrap().compare(&BigInt::from(0)));
+    }
+
+    #[test]
+    fn inexact_builtins_are_approximated() {
+        let z: Box<dyn Val> = Box::new(Complex::new(BigRational::from(BigInt::from(1)), BigRational::from(BigInt::from(1))));
+
+        assert!(matches!(modulus(&[z]), Err(Error::Runtime(RuntimeError::Approximated(_)))));
+        assert!(modulus(&[Box::new(Complex::new(BigRational::from(BigInt::from(3)), BigRational::from(BigInt::from(4))))]).unwrap().compare(&BigInt::from(5)));
+    }
+
+    #[test]
+    fn assertions() {
+        assert!(assert(&[Box::new(true)]).unwrap().compare(&Unit));
+        assert!(assert(&[Box::new(false)]).unwrap_err().to_string().starts_with("Assertion failed"));
+        assert!(assert(&ints(&[1])).is_err());
+
+        assert!(assert_eq(&ints(&[2, 2])).is_ok());
+        assert!(assert_eq(&ints(&[1, 2])).unwrap_err().to_string().starts_with("Assertion failed: '1' does not equal '2'"));
+    }
+
+    #[test]
+    fn integers_in_other_bases() {
+        assert!(hex(&ints(&[255])).unwrap().compare(&String::from("ff")));
+        assert!(hex(&ints(&[-16])).unwrap().compare(&String::from("-10")));
+        assert!(bin(&ints(&[10])).unwrap().compare(&String::from("1010")));
+        assert!(hex(&[real(1, 2)]).is_err());
+        assert!(bin(&[Box::new(String::from("10"))]).is_err());
+
+        // Integers are still written as fractions
+        assert!(frac(&[real(6, 4)]).unwrap().compare(&String::from("3/2")));
+        assert!(frac(&ints(&[2])).unwrap().compare(&String::from("2/1")));
+    }
+
+    #[test]
+    fn sign_of_reals() {
+        assert!(sign(&ints(&[-3])).unwrap().compare(&BigInt::from(-1)));
+        assert!(sign(&ints(&[0])).unwrap().compare(&BigInt::from(0)));
+        assert!(sign(&[real(2, 3)]).unwrap().compare(&BigInt::from(1)));
+        assert!(sign(&[Box::new(Complex::new(BigRational::from(BigInt::from(-2)), BigRational::zero()))]).unwrap().compare(&BigInt::from(-1)));
+
+        // Complex numbers off the real axis have no rational sign
+        assert!(sign(&[Box::new(Complex::new(BigRational::zero(), BigRational::from(BigInt::from(1))))]).is_err());
+    }
+
+    #[test]
+    fn round_to_decimal_places() {
+        assert!(round_to(&[real(1, 3), Box::new(BigInt::from(2))]).unwrap().compare(real(33, 100).as_ref()));
+        assert!(round_to(&[real(1, 3), Box::new(BigInt::from(4))]).unwrap().compare(real(3333, 10000).as_ref()));
+
+        // Halves round away from zero
+        assert!(round_to(&[real(-1, 8), Box::new(BigInt::from(2))]).unwrap().compare(real(-13, 100).as_ref()));
+        assert!(round_to(&[real(5, 2), Box::new(BigInt::from(0))]).unwrap().compare(&BigInt::from(3)));
+
+        assert!(round_to(&[real(1, 3), Box::new(BigInt::from(-1))]).is_err());
+        assert!(round_to(&[real(1, 3), real(1, 2)]).is_err());
+    }
+
+    #[test]
+    fn complex_from_parts() {
+        let z = complex(&ints(&[3, 4])).unwrap();
+
+        assert!(z.compare(&Complex::new(BigRational::from(BigInt::from(3)), BigRational::from(BigInt::from(4)))));
+        assert!(modulus(&[z]).unwrap().compare(&BigInt::from(5)));
+        assert!(modulus(&ints(&[-2])).unwrap().compare(&BigInt::from(2)));
+        assert!(complex(&[Box::new(BigInt::from(1)), Box::new(Complex::new(BigRational::zero(), BigRational::from(BigInt::from(1))))]).is_err());
+
+        // Only angles on the positive real axis are exact
+        assert!(arg(&ints(&[2])).unwrap().compare(&BigInt::from(0)));
+        assert!(matches!(arg(&ints(&[-1])), Err(Error::Runtime(RuntimeError::Approximated(_)))));
+        assert!(arg(&ints(&[0])).is_err());
+    }
+
+    #[test]
+    fn elements_are_a_sorted_tuple() {
+        let sorted = elements(&[set(ints(&[3, 1, 2]))]).unwrap();
+
+        assert!(sorted.is_tup());
+        assert!(sorted.compare(&Tuple(ints(&[1, 2, 3]))));
+        assert!(elements(&[Box::new(Rc::new(CanonSet::Infinite(InfiniteSet::Nat)))]).is_err());
+    }
+
+    #[test]
+    fn replicate_makes_copies() {
+        assert!(replicate(&ints(&[0, 3])).unwrap().compare(&Tuple(ints(&[0, 0, 0]))));
+        assert!(replicate(&ints(&[7, 0])).unwrap().compare(&Tuple(vec![])));
+        assert!(replicate(&ints(&[0, -1])).is_err());
+        assert!(replicate(&[Box::new(BigInt::from(0)), real(1, 2)]).is_err());
+    }
+
+    #[test]
+    fn indexed_pairs_each_element_with_its_index() {
+        let pairs = indexed(&[strings(&["a", "b"])]).unwrap();
+        let Some(Tuple(pairs)) = pairs.downcast_ref() else {
+            panic!("expected a tuple, but got '{pairs}'")
+        };
+
+        assert_eq!(pairs.len(), 2);
+
+        for (i, (pair, element)) in pairs.iter().zip(["a", "b"]).enumerate() {
+            let mut expected = ints(&[i as i32]);
+            expected.push(Box::new(element.to_owned()));
+
+            assert!(pair.compare(&Tuple(expected)), "unexpected pair '{pair}'");
+        }
+
+        assert!(indexed(&[string("ab")]).is_err());
+    }
+
+    #[test]
+    fn flatten_is_the_union_of_the_sets() {
+        let sets = set(vec![set(ints(&[1, 2])), set(ints(&[2, 3]))]);
+
+        assert!(flatten(&[sets]).unwrap().compare(set(ints(&[1, 2, 3])).as_ref()));
+        assert!(flatten(&[set(vec![])]).unwrap().compare(&Rc::new(CanonSet::empty())));
+        assert!(flatten(&[set(vec![set(ints(&[1])), Box::new(BigInt::from(2))])]).is_err());
+    }
+
+    #[test]
+    fn intersect_all_is_the_intersection_of_the_sets() {
+        let sets = set(vec![set(ints(&[1, 2, 3])), set(ints(&[2, 3, 4]))]);
+
+        assert!(intersect_all(&[sets]).unwrap().compare(set(ints(&[2, 3])).as_ref()));
+        assert!(intersect_all(&[set(vec![set(ints(&[1]))])]).unwrap().compare(set(ints(&[1])).as_ref()));
+        assert!(intersect_all(&[set(vec![])]).is_err());
+        assert!(intersect_all(&[set(ints(&[1, 2]))]).is_err());
+    }
+
+    #[test]
+    fn substring_checks() {
+        assert!(starts_with(&[string("hello"), string("he")]).unwrap().compare(&true));
+        assert!(starts_with(&[string("hello"), string("lo")]).unwrap().compare(&false));
+        assert!(ends_with(&[string("hello"), string("lo")]).unwrap().compare(&true));
+        assert!(contains(&[string("hello"), string("ell")]).unwrap().compare(&true));
+        assert!(contains(&[string("hello"), string("xyz")]).unwrap().compare(&false));
+        assert!(contains(&[string("hello"), string("")]).unwrap().compare(&true));
+        assert!(starts_with(&[string("1"), Box::new(BigInt::from(1))]).is_err());
+    }
+
+    #[test]
+    fn case_and_whitespace() {
+        assert!(upper(&[string("abc")]).unwrap().compare(&String::from("ABC")));
+        assert!(upper(&[string("straße")]).unwrap().compare(&String::from("STRASSE")));
+        assert!(lower(&[string("ÀB")]).unwrap().compare(&String::from("àb")));
+        assert!(trim(&[string("  x  ")]).unwrap().compare(&String::from("x")));
+        assert!(trim(&[string("\t\n")]).unwrap().compare(&String::new()));
+        assert!(upper(&ints(&[1])).is_err());
+    }
+
+    #[test]
+    fn split_and_join() {
+        assert!(split(&[string("a,b,c"), string(",")]).unwrap().compare(strings(&["a", "b", "c"]).as_ref()));
+        assert!(split(&[string("a,,"), string(",")]).unwrap().compare(strings(&["a", "", ""]).as_ref()));
+        assert!(split(&[string("abc"), string("")]).is_err());
+        assert!(join(&[strings(&["a", "b"]), string("-")]).unwrap().compare(&String::from("a-b")));
+        assert!(join(&[strings(&[]), string("-")]).unwrap().compare(&String::new()));
+
+        // Separators and joined elements must be strings
+        assert!(join(&[strings(&["a"]), Box::new(BigInt::from(1))]).is_err());
+        assert!(join(&[Box::new(Tuple(ints(&[1, 2]))), string("-")]).is_err());
+    }
+
+    #[test]
+    fn format_fills_placeholders_in_order() {
+        let mut args = vec![string("x = {}, y = {}")];
+        args.extend(ints(&[1, 2]));
+
+        assert!(format(&args).unwrap().compare(&String::from("x = 1, y = 2")));
+        assert!(format(&[string("{{}} {}"), real(1, 2)]).unwrap().compare(&String::from("{} 1/2")));
+
+        // Too few or too many values
+        assert!(format(&[string("{} {}"), string("a")]).unwrap_err().to_string().starts_with("'format' has 2 placeholder(s), but was given 1 value(s)"));
+        assert!(format(&args[..2]).is_err());
+        assert!(format(&[string("{}"), string("a"), string("b")]).is_err());
+    }
+
+    #[test]
+    fn sqrt_of_perfect_squares() {
+        assert!(sqrt(&ints(&[9])).unwrap().compare(&BigInt::from(3)));
+        assert!(sqrt(&[real(9, 4)]).unwrap().compare(real(3, 2).as_ref()));
+        assert!(sqrt(&ints(&[-1])).unwrap().compare(&Complex::new(BigRational::zero(), BigRational::from(BigInt::from(1)))));
+        assert!(sqrt(&ints(&[0])).unwrap().compare(&BigInt::from(0)));
+        assert!(sqrt(&ints(&[2])).unwrap_err().to_string().contains("'2' is not one"));
+    }
+
+    #[test]
+    fn native_functions_check_their_arity() {
+        let native_abs = NativeFunc::new("abs", 1, abs);
+        let native_gcd = NativeFunc::new("gcd", 2, gcd);
+        let args = |values: &[i32]| ints(values).into_iter().map(Some).collect::<Vec<_>>();
+
+        assert!(native_abs.call(&args(&[-3])).unwrap().compare(&BigInt::from(3)));
+        assert!(native_abs.call(&[Some(real(-1, 2))]).unwrap().compare(real(1, 2).as_ref()));
+        assert!(native_gcd.call(&args(&[12, 18])).unwrap().compare(&BigInt::from(6)));
+
+        assert!(native_gcd.call(&args(&[12])).unwrap_err().to_string().starts_with("Builtin function 'gcd' takes 2 arguments, but 1 were given"));
+        assert!(native_abs.call(&args(&[1, 2])).is_err());
+        assert!(native_gcd.call(&[Some(Box::new(BigInt::from(1))), None]).is_err());
+    }
+}
@@ -2,32 +2,140 @@ use std::{io, path};
 
 #[derive(Debug, Clone)]
 pub struct Config {
-    mode: Mode
+    mode: Mode,
+    max_set_size: Option<usize>,
+    strict_equality: bool,
+    exact: bool,
+    dump_ast: bool
 }
 
 impl Config {
     pub fn build<I: Iterator<Item = String>>(args: I) -> io::Result<Self> {
-        Ok(match args.skip(1).next() {
-            Some(arg) => Self {
-                mode: if path::Path::new(&arg).try_exists()? {
-                    Mode::File(arg)
-                } else {
-                    return Err(io::Error::new(io::ErrorKind::NotFound, "File not found"))
+        let mut args = args.skip(1);
+        let mut paths = Vec::new();
+        let mut eval = None;
+        let mut max_set_size = None;
+        let mut strict_equality = false;
+        let mut exact = false;
+        let mut dump_ast = false;
+
+        while let Some(arg) = args.next() {
+            if arg == "--max-set-size" {
+                max_set_size = Some(args
+                    .next()
+                    .and_then(|size| size.parse().ok())
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "expected a number after '--max-set-size'"))?);
+            } else if arg == "-e" || arg == "--eval" {
+                eval = Some(args
+                    .next()
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("expected a program after '{arg}'")))?);
+            } else if arg == "--strict-eq" {
+                strict_equality = true;
+            } else if arg == "--exact" {
+                exact = true;
+            } else if arg == "--ast" {
+                dump_ast = true;
+            } else {
+                paths.push(arg);
+            }
+        }
+
+        let mode = match (paths.is_empty(), eval) {
+            (false, Some(_)) => return Err(io::Error::new(io::ErrorKind::InvalidInput, "expected either files or '--eval', not both")),
+            (true, Some(source)) => Mode::Eval(source),
+            (false, None) => {
+                for arg in &paths {
+                    if !path::Path::new(arg).try_exists()? {
+                        return Err(io::Error::new(io::ErrorKind::NotFound, format!("cannot open '{arg}': No such file")))
+                    }
                 }
+
+                Mode::Files(paths)
             },
-            None => Self {
-                mode: Mode::Repl
-            }
-        })
+            (true, None) => Mode::Repl
+        };
+
+        Ok(Self { mode, max_set_size, strict_equality, exact, dump_ast })
     }
 
     pub fn mode(&self) -> &Mode {
         &self.mode
     }
+
+    /// The largest finite set the interpreter may build, if overridden with `--max-set-size`.
+    pub fn max_set_size(&self) -> Option<usize> {
+        self.max_set_size
+    }
+
+    /// Whether `==` should also compare representations, as set by `--strict-eq`.
+    pub fn strict_equality(&self) -> bool {
+        self.strict_equality
+    }
+
+    /// Whether results that can only be approximated are errors, as set by `--exact`.
+    pub fn exact(&self) -> bool {
+        self.exact
+    }
+
+    /// Whether to show the parsed program as source before running it, as set by `--ast`.
+    pub fn dump_ast(&self) -> bool {
+        self.dump_ast
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum Mode {
     Repl,
-    File(String)
+    /// Files run in order in one environment, so later files can use what earlier ones define.
+    Files(Vec<String>),
+    /// A program given on the command line with `-e` or `--eval`.
+    Eval(String)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(args: &[&str]) -> io::Result<Config> {
+        Config::build(["math-lang"].iter().chain(args).map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn bad_arguments_are_errors() {
+        for (args, message) in [
+            (&["missing.math"][..], "cannot open 'missing.math': No such file"),
+            (&["--max-set-size"], "expected a number after '--max-set-size'"),
+            (&["--max-set-size", "lots"], "expected a number after '--max-set-size'"),
+            (&["-e"], "expected a program after '-e'"),
+            (&["Cargo.toml", "-e", "1"], "expected either files or '--eval', not both")
+        ] {
+            let Err(err) = build(args) else {
+                panic!("expected {args:?} to be an error")
+            };
+
+            assert_eq!(err.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn strict_equality_is_off_unless_asked_for() {
+        assert!(!build(&["-e", "2 == 2.0"]).unwrap().strict_equality());
+        assert!(build(&["--strict-eq", "-e", "2 == 2.0"]).unwrap().strict_equality());
+    }
+
+    #[test]
+    fn eval_takes_the_next_argument() {
+        for flag in ["-e", "--eval"] {
+            assert!(matches!(build(&[flag, "2+2"]).unwrap().mode(), Mode::Eval(source) if source == "2+2"));
+        }
+
+        assert!(matches!(build(&[]).unwrap().mode(), Mode::Repl));
+    }
+
+    #[test]
+    fn every_path_is_kept_in_order() {
+        let config = build(&["src/lib.rs", "Cargo.toml"]).unwrap();
+
+        assert!(matches!(config.mode(), Mode::Files(paths) if paths == &["src/lib.rs", "Cargo.toml"]));
+    }
 }
@@ -0,0 +1,38 @@
+/// Renders `message` followed by the offending line of `source` with a caret under column `col`.
+///
+/// Both `line` and `col` are 1-based. Tabs before the caret are kept so it lines up with the source.
+pub fn render(source: &str, line: usize, col: usize, message: &str) -> String {
+    let text = source.lines().nth(line.saturating_sub(1)).unwrap_or("");
+    let gutter = " ".repeat(line.to_string().len());
+    let padding: String = text.chars()
+        .take(col.saturating_sub(1))
+        .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    format!(
+        "{message}\n{gutter}--> line {line}, column {col}\n{gutter} |\n{line} | {text}\n{gutter} | {padding}^"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caret_is_under_the_column() {
+        assert_eq!(
+            render("x = 1\ny = 3 4", 2, 7, "error: oops"),
+            "error: oops\n --> line 2, column 7\n  |\n2 | y = 3 4\n  |       ^"
+        );
+    }
+
+    #[test]
+    fn tabs_and_long_line_numbers_keep_the_caret_aligned() {
+        let source = format!("{}\tx = @", "\n".repeat(11));
+
+        assert_eq!(
+            render(&source, 12, 6, "error: oops"),
+            "error: oops\n  --> line 12, column 6\n   |\n12 | \tx = @\n   | \t    ^"
+        );
+    }
+}
@@ -1,11 +1,9 @@
-use std::borrow::Borrow;
-use std::cell::{Ref, RefCell};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
-use std::ops::BitOr;
 use std::rc::Rc;
 
-use crate::set::{canon, CanonSet, Set, SetPool};
+use crate::set::CanonSet;
 use crate::value::Val;
 
 /// What the symbol map stores.
@@ -18,13 +16,26 @@ pub enum SymStore {
     FuncType(Vec<Rc<CanonSet>>, Rc<CanonSet>)
 }
 
-impl SymStore {
-    /// Returns if it is a subset of the given set.
-    fn subset_of(&self, set: Rc<CanonSet>) -> bool {
-        match self {
-            Self::Value(value) => set.contains(value),
-            Self::Type(typeset) => typeset.is_subset(&set),
-            Self::FuncType(_, _) => false // todo
+/// The kind of a symbol, along with a short description of it, for tooling like autocompletion.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymKind {
+    /// A symbol with a value, described by the value itself.
+    Value(String),
+    /// A symbol declared to be in some set, but not yet assigned, described by the set.
+    Type(String),
+    /// A function declared to map some sets to another, but not yet assigned, described like `Int, Real -> Real`.
+    FuncType(String)
+}
+
+impl From<&SymStore> for SymKind {
+    fn from(value: &SymStore) -> Self {
+        match value {
+            SymStore::Value(value) => Self::Value(value.display()),
+            SymStore::Type(typeset) => Self::Type(typeset.to_string()),
+            SymStore::FuncType(args, codomain) => Self::FuncType(format!(
+                "{} -> {codomain}",
+                args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>().join(", ")
+            ))
         }
     }
 }
@@ -49,7 +60,7 @@ impl Env {
     }
 
     pub fn get(&self, name: &str) -> Option<SymStore> {
-        if self.symbols.get(name).is_some() {
+        if self.symbols.contains_key(name) {
             self.symbols.get(name).map(|name| name.to_owned())
         } else if let Some(env) = &self.parent {
             RefCell::borrow(env).get(name)
@@ -72,6 +83,18 @@ impl Env {
         }
     }
 
+    /// Returns the symbols defined directly in this [`Env`] (not its parents), sorted by name.
+    pub fn symbols(&self) -> Vec<(String, SymKind)> {
+        let mut symbols = self.symbols
+            .iter()
+            .map(|(name, store)| (name.to_owned(), SymKind::from(store)))
+            .collect::<Vec<_>>();
+
+        symbols.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        symbols
+    }
+
     /// Returns if the given key is in the [`Env`].
     pub fn contains_key(&self, name: &str) -> bool {
         self.symbols.contains_key(name)
@@ -79,10 +102,7 @@ impl Env {
 
     /// Returns if the symbol has a value assigned to it
     pub fn is_sym_assigned(&self, name: &str) -> bool {
-        match self.symbols.get(name) {
-            Some(SymStore::Value(_)) => true,
-            _ => false
-        }
+        matches!(self.symbols.get(name), Some(SymStore::Value(_)))
     }
 
     /// `set` must already be interned.
@@ -115,7 +135,7 @@ impl Env {
 impl fmt::Debug for Env {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Env")
-            .field("parent", if let None = self.parent {
+            .field("parent", if self.parent.is_none() {
                 &None::<()>
             } else {
                 &Some("recursive [Env]")
@@ -124,3 +144,39 @@ impl fmt::Debug for Env {
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use num::BigInt;
+
+    use super::*;
+    use crate::set::InfiniteSet;
+
+    #[test]
+    fn symbols_are_looked_up_in_parents() {
+        let parent = Rc::new(RefCell::new(Env::new(None)));
+        parent.borrow_mut().insert_sym(String::from("x"), Box::new(BigInt::from(1)));
+        parent.borrow_mut().insert_sym(String::from("Nat"), Box::new(Rc::new(CanonSet::Infinite(InfiniteSet::Nat))));
+
+        let mut child = Env::new(Some(Rc::clone(&parent)));
+        child.insert_sym_type(String::from("y"), Rc::new(CanonSet::Infinite(InfiniteSet::Int)));
+
+        assert!(matches!(child.get("x"), Some(SymStore::Value(value)) if value.compare(&BigInt::from(1))));
+        assert!(child.get_set("Nat").is_some());
+        assert!(!child.contains_key("x"));
+        assert!(!child.is_sym_assigned("y"));
+        assert!(child.get("z").is_none());
+    }
+
+    #[test]
+    fn symbols_are_sorted_by_name() {
+        let mut env = Env::new(None);
+        env.insert_sym(String::from("b"), Box::new(BigInt::from(2)));
+        env.insert_sym_type(String::from("a"), Rc::new(CanonSet::Infinite(InfiniteSet::Nat)));
+
+        assert_eq!(env.symbols(), vec![
+            (String::from("a"), SymKind::Type(String::from("Nat"))),
+            (String::from("b"), SymKind::Value(String::from("2")))
+        ]);
+    }
+}
@@ -1,16 +1,145 @@
-use std::io;
+use std::{fmt, io};
+
+use crate::value::Val;
 
 #[derive(Debug)]
 pub enum Error {
     Io(io::Error),
-    Lexer(LexError)
+    Lexer(LexError),
+    Parser(ParseError),
+    Runtime(RuntimeError)
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Lexer(err) => write!(f, "{err}"),
+            Self::Parser(err) => write!(f, "{err}"),
+            Self::Runtime(err) => write!(f, "{err}")
+        }
+    }
+}
+
+impl Error {
+    /// Returns the 1-based line and column the error points at, if it has one.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self {
+            Self::Lexer(LexError::UnclosedString { line, col }) |
+            Self::Lexer(LexError::UnclosedComment { line, col }) => Some((*line, *col)),
+            Self::Parser(err) => Some(err.position()),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(value: RuntimeError) -> Self {
+        Self::Runtime(value)
+    }
 }
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum LexError {
-    UnclosedString,
+    UnclosedString { line: usize, col: usize },
+    UnclosedComment { line: usize, col: usize },
     UnclosedParenthesis,
     UnclosedBracket
 }
 
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedString { .. } => write!(f, "unclosed string literal"),
+            Self::UnclosedComment { .. } => write!(f, "unclosed block comment"),
+            Self::UnclosedParenthesis => write!(f, "unclosed parenthesis"),
+            Self::UnclosedBracket => write!(f, "unclosed bracket")
+        }
+    }
+}
+
+/// A syntax error, at the line and column of the token where it was found.
+#[derive(Debug)]
+pub enum ParseError {
+    /// A `:` with nothing after it on its line, at the `:` itself.
+    ExpectedType { line: usize, col: usize },
+    /// A function definition naming the same parameter twice, at its second use.
+    DuplicateParameter { name: String, line: usize, col: usize },
+    /// Any other syntax error.
+    Failed { line: usize, col: usize, message: String }
+}
+
+impl ParseError {
+    pub fn new(line: usize, col: usize, message: impl Into<String>) -> Self {
+        Self::Failed {
+            line,
+            col,
+            message: message.into()
+        }
+    }
+
+    /// Returns the 1-based line and column of the error.
+    pub fn position(&self) -> (usize, usize) {
+        match self {
+            Self::ExpectedType { line, col } |
+            Self::DuplicateParameter { line, col, .. } |
+            Self::Failed { line, col, .. } => (*line, *col)
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ExpectedType { line, .. } => write!(f, "Expected a type after ':', but the line ended (line {line})"),
+            Self::DuplicateParameter { name, line, .. } => write!(f, "Duplicate parameter '{name}' in function definition (line {line})"),
+            Self::Failed { line, message, .. } => write!(f, "{message} (line {line})")
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RuntimeError {
+    SetTooLarge { size: usize, max: usize },
+    /// The result of a builtin that could only be approximated, which is up to the interpreter to accept, as it may be keeping results exact.
+    Approximated(Box<dyn Val>),
+    /// Any other failure while running, with the source of the statement being run once it is known.
+    Failed { message: String, expr: Option<String> }
+}
+
+impl RuntimeError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self::Failed {
+            message: message.into(),
+            expr: None
+        }
+    }
+
+    /// Records the expression where the error happened, unless an inner one was already recorded.
+    pub fn within(self, source: &impl fmt::Display) -> Self {
+        match self {
+            Self::Failed { message, expr: None } => Self::Failed { message, expr: Some(source.to_string()) },
+            err => err
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SetTooLarge { size, max } => write!(f, "set of {size} elements exceeds the maximum set size of {max}"),
+            Self::Approximated(approx) => write!(f, "result could only be approximated as '{approx}'"),
+            Self::Failed { message, expr: None } => write!(f, "{message}"),
+            Self::Failed { message, expr: Some(expr) } => write!(f, "{message} (in '{expr}')")
+        }
+    }
+}
+
 pub type Result<T> = core::result::Result<T, Error>;
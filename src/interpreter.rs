@@ -1,23 +1,69 @@
-use std::borrow::Borrow;
 use std::cell::RefCell;
 use std::collections::HashSet;
+use std::fmt::{self, Debug};
+use std::fs;
+use std::io::{self, Write};
 use std::ops::Neg;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 use num::bigint::Sign;
-use num::{BigInt, BigRational, Complex, One, Zero};
+use num::{BigInt, BigRational, Complex, One, ToPrimitive, Zero};
 use num::pow::Pow;
 
 use crate::ast::{expr, expr::*, stmt::*};
-use crate::environment::{Env, SymStore};
-use crate::set::{self, canon, CanonSet, FiniteSet, InfiniteSet, Set, SetPool};
+use crate::builtins;
+use crate::environment::{Env, SymKind, SymStore};
+use crate::iter::ValIterator;
+use crate::error::{self, Error, RuntimeError};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::set::{canon, CanonSet, FiniteSet, InfiniteSet, Set, SetPool};
 use crate::token::{Token, TokenKind};
-use crate::types;
-use crate::value::{Func, Tuple, Val};
+use crate::value::{coerce_pair, Cardinality, Func, Matrix, Module, NativeFunc, NumKind, NumVal, Tuple, Unit, Val};
+
+/// The largest finite set the interpreter builds unless told otherwise.
+pub const DEFAULT_MAX_SET_SIZE: usize = 1_000_000;
+
+/// How an interpreter runs, which is carried into every scope and function call inside it.
+#[derive(Clone)]
+pub struct Settings {
+    /// The largest finite set that may be built.
+    pub max_set_size: usize,
+    /// Whether `==` and `!=` also compare representations, so that `2 == 2.0` is false.
+    pub strict_equality: bool,
+    /// Whether results that can only be approximated, like `(1/2)^(2^40)`, are errors instead.
+    pub exact: bool,
+    /// Where the values of logged statements are written, which is shared rather than copied, so a block writes where its parent does.
+    pub output: Rc<RefCell<Box<dyn Write>>>
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            max_set_size: DEFAULT_MAX_SET_SIZE,
+            strict_equality: false,
+            exact: false,
+            output: Rc::new(RefCell::new(Box::new(io::stdout())))
+        }
+    }
+}
+
+impl Debug for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Settings")
+            .field("max_set_size", &self.max_set_size)
+            .field("strict_equality", &self.strict_equality)
+            .field("exact", &self.exact)
+            .finish_non_exhaustive()
+    }
+}
 
-#[derive(Debug)]
 pub struct Interpreter {
     env: Rc<RefCell<Env>>,
-    set_pool: SetPool
+    set_pool: SetPool,
+    settings: Settings,
+    /// The files being imported, innermost last, so that a file importing itself (perhaps indirectly) is caught.
+    importing: Vec<PathBuf>
 }
 
 macro_rules! insert_set {
@@ -34,6 +80,29 @@ macro_rules! insert_set {
     };
 }
 
+macro_rules! insert_native {
+    (
+        $env:ident ;
+        $name:ident /
+        $arity:literal
+    ) => {
+        $env.insert_sym(
+            String::from(stringify!($name)),
+            Box::new(NativeFunc::new(stringify!($name), $arity, builtins::$name))
+        )
+    };
+    (
+        $env:ident ;
+        $name:ident /
+        $arity:literal ..
+    ) => {
+        $env.insert_sym(
+            String::from(stringify!($name)),
+            Box::new(NativeFunc::variadic(stringify!($name), $arity, builtins::$name))
+        )
+    };
+}
+
 impl Interpreter {
     pub fn new() -> Self {
         let mut set_pool = SetPool::new();
@@ -52,56 +121,230 @@ impl Interpreter {
         // Text Types (implementing class Text?)
         insert_set!(env; Str: CanonSet::Infinite(InfiniteSet::Str); set_pool);
 
+        // Function Introspection
+        insert_native!(env; domain / 1);
+        insert_native!(env; codomain / 1);
+
+        // Set Queries
+        insert_native!(env; disjoint / 2);
+        insert_native!(env; setof / 1);
+        insert_native!(env; elements / 1);
+        insert_native!(env; flatten / 1);
+        insert_native!(env; intersect_all / 1);
+        env.insert_sym(String::from("typeof"), Box::new(NativeFunc::new("typeof", 1, builtins::type_of)));
+
+        // Tuples
+        insert_native!(env; replicate / 2);
+        insert_native!(env; indexed / 1);
+
+        // Ordering
+        insert_native!(env; sort / 1);
+        insert_native!(env; min / 1..);
+        insert_native!(env; max / 1..);
+
+        // Strings
+        insert_native!(env; starts_with / 2);
+        insert_native!(env; ends_with / 2);
+        insert_native!(env; contains / 2);
+        insert_native!(env; upper / 1);
+        insert_native!(env; lower / 1);
+        insert_native!(env; trim / 1);
+        insert_native!(env; split / 2);
+        insert_native!(env; join / 2);
+        insert_native!(env; format / 1..);
+
+        // Numbers
+        insert_native!(env; abs / 1);
+        insert_native!(env; gcd / 2);
+        insert_native!(env; sign / 1);
+        insert_native!(env; round_to / 2);
+        insert_native!(env; complex / 2);
+        insert_native!(env; modulus / 1);
+        insert_native!(env; sqrt / 1);
+        insert_native!(env; arg / 1);
+
+        // Formatting
+        insert_native!(env; hex / 1);
+        insert_native!(env; bin / 1);
+        insert_native!(env; frac / 1);
+
+        // Testing
+        insert_native!(env; assert / 1);
+        insert_native!(env; assert_eq / 2);
+
         Self {
             env: Rc::new(RefCell::new(env)),
-            set_pool
+            set_pool,
+            settings: Settings::default(),
+            importing: vec![]
         }
     }
 
-    pub fn with_env(env: &Rc<RefCell<Env>>) -> Self {
+    /// Creates an interpreter for `env`, like the one a function body runs in, which runs with `settings`.
+    pub fn with_settings(env: &Rc<RefCell<Env>>, settings: &Settings) -> Self {
         Self {
             env: Rc::clone(env),
-            set_pool: SetPool::new()
+            set_pool: SetPool::new(),
+            settings: settings.clone(),
+            importing: vec![]
+        }
+    }
+
+    /// Creates an interpreter for a scope inside this one, with the same settings.
+    fn scoped(&self, env: Env) -> Self {
+        Self {
+            importing: self.importing.clone(),
+            ..Self::with_settings(&Rc::new(RefCell::new(env)), &self.settings)
         }
     }
 
-    pub fn interpret<'s>(&mut self, stmts: &'s [Box<dyn Stmt>]) {
+    /// Sets the largest finite set the interpreter may build.
+    pub fn set_max_set_size(&mut self, max: usize) {
+        self.settings.max_set_size = max;
+    }
+
+    /// Sets where the values of logged statements are written, which is stdout by default.
+    /// 
+    /// This also changes it for the scopes and functions this interpreter already made, since they share it.
+    pub fn set_output(&mut self, output: Box<dyn Write>) {
+        *self.settings.output.borrow_mut() = output;
+    }
+
+    /// Sets whether `==` and `!=` also compare representations, so that `2 == 2.0` is false.
+    pub fn set_strict_equality(&mut self, strict: bool) {
+        self.settings.strict_equality = strict;
+    }
+
+    /// Sets whether results that can only be approximated, like `(1/2)^(2^40)`, are errors instead.
+    pub fn set_exact(&mut self, exact: bool) {
+        self.settings.exact = exact;
+    }
+
+    /// Returns the top-level bindings, sorted by name.
+    pub fn bindings(&self) -> Vec<(String, SymKind)> {
+        RefCell::borrow(&self.env).symbols()
+    }
+
+    /// Executes the statements in order, stopping at the first one that fails.
+    pub fn interpret(&mut self, stmts: &[Box<dyn Stmt>]) -> error::Result<()> {
         for stmt in stmts {
-            self.execute_stmt(stmt);
+            self.execute_stmt(stmt.as_ref())?;
+        }
+
+        Ok(())
+    }
+
+    /// Executes a statement, and returns its value, which is `()` for declarations.
+    /// 
+    /// An error records the statement it happened in, if nothing inside it was more specific.
+    pub fn execute_stmt(&mut self, stmt: &dyn Stmt) -> error::Result<Box<dyn Val>> {
+        if let Some(ExprStmt(expr, _)) = stmt.downcast_ref() {
+            self.execute_expr_stmt(stmt).map_err(|err| match err {
+                Error::Runtime(err) => Error::Runtime(err.within(expr)),
+                err => err
+            })
+        } else if let Some(Block(stmts)) = stmt.downcast_ref() {
+            self.execute_block(stmts)
+        } else if let Some(Import(path, alias)) = stmt.downcast_ref() {
+            self.execute_import(path, alias)?;
+
+            Ok(Box::new(Unit))
+        } else {
+            Self::error(format!("Cannot execute the statement '{stmt}'"))
+        }
+    }
+
+    /// Replaces the name in `module.name` with the value it has in the module.
+    fn member_expr(module: &Module, right: &dyn Expr) -> error::Result<Box<dyn Expr>> {
+        let Some(Symbol(name)) = right.downcast_ref() else {
+            return Self::error(format!("Expected a name after '{module}.'"))
+        };
+
+        // A function is grouped, so that it still reads as one when called
+        match module.get(name) {
+            Some(value) if value.downcast_ref::<Func>().is_some() => Ok(Box::new(Group(Box::new(Literal(value))))),
+            Some(value) => Ok(Box::new(Literal(value))),
+            None => Self::error(format!("'{name}' is not defined in '{}'", module.path()))
+        }
+    }
+
+    /// Runs the file at `path` in a fresh environment, and binds what it defines to `alias` as a [`Module`]. What the file logs isn't shown.
+    /// 
+    /// The path is relative to the file doing the import, or to the working directory for the main program.
+    fn execute_import(&mut self, path: &str, alias: &str) -> error::Result<()> {
+        if RefCell::borrow(&self.env).is_sym_assigned(alias) {
+            return Self::error(format!("Variable {alias} cannot be reassigned"))
+        }
+
+        let dir = self.importing.last().and_then(|file| file.parent()).map(Path::to_path_buf).unwrap_or_default();
+        let file = match fs::canonicalize(dir.join(path)) {
+            Ok(file) => file,
+            Err(err) => return Self::error(format!("Cannot import '{path}': {err}"))
+        };
+
+        if self.importing.contains(&file) {
+            return Self::error(format!("Cannot import '{path}', because it is already being imported"))
         }
+
+        // Errors inside the file are reported as a failed import, as their lines are in the other file
+        let failed = |err: Error| RuntimeError::new(format!("Cannot import '{path}': {err}"));
+
+        let source = fs::read_to_string(&file).map_err(|err| failed(err.into()))?;
+        let tokens = Lexer::new(source.as_bytes()).lex().map_err(failed)?;
+        let ast = Parser::new(&tokens).parse().map_err(failed)?;
+
+        // The file's own symbols go in a child of its builtins, so only they are part of the module
+        let builtins = Self::new().env;
+        let mut module = self.scoped(Env::new(Some(builtins)));
+        module.importing.push(file);
+        module.settings.output = Rc::new(RefCell::new(Box::new(io::sink())));
+        module.interpret(ast.stmts()).map_err(failed)?;
+
+        self.env.borrow_mut().insert_sym(alias.to_owned(), Box::new(Module::new(path.to_owned(), module.env)));
+
+        Ok(())
     }
 
-    pub fn execute_stmt(&mut self, stmt: &Box<dyn Stmt>) {
+    fn execute_expr_stmt(&mut self, stmt: &dyn Stmt) -> error::Result<Box<dyn Val>> {
         if let Some(ExprStmt(expr, is_to_log)) = stmt.downcast_ref() {
             // assign
             if let Some(Assign(Symbol(name), right)) = expr.downcast_ref() {
-                let value = self.execute_assign(name, right);
+                let value = self.execute_assign(name, right.as_ref())?;
 
                 if *is_to_log {
-                    println!("{name} = {value}")
+                    self.write_line(format_args!("{name} = {}", value.display()))?;
                 }
+
+                Ok(value)
             // typed assign
             } else if let Some(TypedAssign(Symbol(name), typeset, right)) = expr.downcast_ref() {
-                self.execute_typed_assign(name, typeset, right);
+                self.execute_typed_assign(name, typeset.as_ref(), right.as_ref())?;
+
+                Ok(Box::new(Unit))
             // type expr : typecast or typedef
             } else if let Some(TypeExpr(value, typeset)) = expr.downcast_ref() {
                 if let Some(Symbol(name)) = value.downcast_ref() {
                     if !RefCell::borrow(&self.env).is_sym_assigned(name) {
-                        let typeset = self.execute_expr(typeset);
+                        let typeset = self.execute_expr(typeset.as_ref())?;
 
                         // type def
                         if let Some(set) = typeset.downcast_ref::<Rc<CanonSet>>() {
                             self.env.borrow_mut().insert_sym_type(name.to_owned(), Rc::clone(&self.set_pool.intern(set)));
-                            return;
+                            return Ok(Box::new(Unit));
                         } else {
-                            panic!("'{typeset}' is not a set")
+                            return Self::error(format!("'{typeset}' is not a set"))
                         }
                     }
                 }
 
                 // type cast
-                println!("{}", todo!());
-            
+                let value = self.execute_cast(value.as_ref(), typeset.as_ref())?;
+
+                if *is_to_log {
+                    self.write_line(format_args!("{}", value.display()))?;
+                }
+
+                Ok(value)
             } else if let Some(FuncTypeExpr(func, arg_types, codom)) = expr.downcast_ref() {
                 if let Some(Symbol(name)) = func.downcast_ref() {
                     /* perhaps there will have to be a check for only defined in the current env
@@ -122,264 +365,630 @@ impl Interpreter {
                         let mut dom_arr = Vec::with_capacity(arg_types.len());
 
                         for typeset in arg_types {
-                            let typeset = self.execute_expr(typeset);
+                            let typeset = self.execute_expr(typeset.as_ref())?;
 
                             if let Some(set) = typeset.downcast_ref::<Rc<CanonSet>>() {
                                 dom_arr.push(set.to_owned());
                             } else {
-                                panic!("'{typeset}' is not a set")
+                                return Self::error(format!("'{typeset}' is not a set"))
                             }
                         }
 
-                        let codom = self.execute_expr(codom);
+                        let codom = self.execute_expr(codom.as_ref())?;
                         if let Some(set) = codom.downcast_ref::<Rc<CanonSet>>() {
                             self.env.borrow_mut().insert_sym_func_type(name.to_owned(), dom_arr, Rc::clone(set));
-                            return;
+                            return Ok(Box::new(Unit));
                         } else {
-                            panic!("'{codom}' is not a set")
+                            return Self::error(format!("'{codom}' is not a set"))
                         }
                     }
-                }
 
-                // function type cast???
-                println!("{}", todo!());
+                    // A function's type can't be changed after it is defined, as its body has already been checked against it
+                    return Self::error(format!("'{name}' is already defined; its type must be declared first"))
+                }
 
+                Self::error(format!("A function type can only be declared for a name, not '{func}'"))
             } else {
-                println!("{}", self.execute_expr(expr));
+                let value = self.execute_expr(expr.as_ref())?;
+
+                // Like a statement, an expression with no result has nothing to show
+                if *is_to_log && value.downcast_ref::<Unit>().is_none() {
+                    self.write_line(format_args!("{}", value.display()))?;
+                }
+
+                Ok(value)
             }
         } else {
-            todo!()
+            unreachable!()
+        }
+    }
+
+    /// Executes the statements of a `do ... end` block in a new scope, which is dropped at the end, and returns the last statement's value.
+    fn execute_block(&mut self, stmts: &[Box<dyn Stmt>]) -> error::Result<Box<dyn Val>> {
+        let mut scoped = self.scoped(Env::new(Some(Rc::clone(&self.env))));
+        let mut value: Box<dyn Val> = Box::new(Unit);
+
+        for stmt in stmts {
+            value = scoped.execute_stmt(stmt.as_ref())?;
         }
+
+        Ok(value)
     }
 
-    pub fn execute_expr<'a>(&mut self, expr: &'a Box<dyn Expr>) -> Box<dyn Val> {
+    /// Creates a runtime error with the given message.
+    fn error<T>(message: impl Into<String>) -> error::Result<T> {
+        Err(Error::Runtime(RuntimeError::new(message)))
+    }
+
+    /// The error for an operator that has no meaning for its operands, which names their types, like `Cannot apply '+' to Unit and Int`.
+    fn operator_error<T>(op: &str, operands: &[&dyn Val]) -> error::Result<T> {
+        let types = operands.iter().map(|operand| operand.type_name()).collect::<Vec<_>>().join(" and ");
+
+        Self::error(format!("Cannot apply '{op}' to {types}"))
+    }
+
+    fn write_line(&mut self, line: fmt::Arguments) -> error::Result<()> {
+        Ok(writeln!(self.settings.output.borrow_mut(), "{line}")?)
+    }
+
+    pub fn execute_expr(&mut self, expr: &dyn Expr) -> error::Result<Box<dyn Val>> {
         if let Some(Literal(lit)) = expr.downcast_ref() {
-            Self::execute_literal(lit)
+            Ok(Self::execute_literal(lit.as_ref()))
         } else if let Some(Symbol(name)) = expr.downcast_ref() {
             if let Some(SymStore::Value(value)) = RefCell::borrow(&self.env).get(name) {
-                value.clone()
+                Ok(value.clone())
             } else {
-                panic!("Variable '{name}' is not defined");
+                Self::error(format!("Variable '{name}' is not defined"))
             }
         } else if let Some(Group(expr)) = expr.downcast_ref::<Group>() {
-            self.execute_expr(expr)
+            self.execute_expr(expr.as_ref())
         } else if let Some(Unary(op, right)) = expr.downcast_ref() {
-            let right = self.execute_expr(right);
+            let right = self.execute_expr(right.as_ref())?;
 
             if let Some(func) = right.downcast_ref::<Func>() {
-                return Box::new(Func::new(
-                    Rc::clone(func.env()), 
-                    func.args(), 
-                    Box::new(Unary(op.clone(), Box::new(Group(func.expr().to_owned())))),
-                    func.codomain()
-                ));
+                return Ok(Box::new(Func::new(
+                    Rc::clone(func.env()),
+                    func.args(),
+                    Box::new(Unary(op.clone(), Box::new(Group(func.expr().clone_expr())))),
+                    func.codomain(),
+                    &self.settings
+                )));
+            }
+
+            match *op.kind() {
+                TokenKind::Plus  => Self::execute_pos(right.as_ref()),
+                TokenKind::Minus => Self::execute_neg(right.as_ref()),
+                TokenKind::Tilde => self.execute_tilde(right.as_ref()),
+                TokenKind::Hash  => Self::execute_cardinality(right.as_ref()),
+                _ => Self::error(format!("Unknown unary operator '{}'", op.lexeme()))
+            }
+        } else if let Some(Postfix(left, op)) = expr.downcast_ref() {
+            let left = self.execute_expr(left.as_ref())?;
+
+            if let Some(func) = left.downcast_ref::<Func>() {
+                return Ok(Box::new(Func::new(
+                    Rc::clone(func.env()),
+                    func.args(),
+                    Box::new(Postfix(Box::new(Group(func.expr().clone_expr())), op.clone())),
+                    func.codomain(),
+                    &self.settings
+                )));
             }
 
             match op.kind() {
-                &TokenKind::Minus => Self::execute_neg(&right),
-                _ => todo!()
+                &TokenKind::Bang => Self::execute_factorial(left.as_ref()),
+                _ => Self::error(format!("Unknown postfix operator '{}'", op.lexeme()))
             }
+        } else if let Some(Index(container, index)) = expr.downcast_ref() {
+            let container = self.execute_expr(container.as_ref())?;
+            let index = self.execute_expr(index.as_ref())?;
+
+            Self::execute_index(container.as_ref(), index.as_ref())
+        } else if let Some(Slice(container, start, end)) = expr.downcast_ref() {
+            let container = self.execute_expr(container.as_ref())?;
+            let start = self.execute_expr(start.as_ref())?;
+            let end = self.execute_expr(end.as_ref())?;
+
+            Self::execute_slice(container.as_ref(), start.as_ref(), end.as_ref())
         } else if let Some(Binary(left, op, right)) = expr.downcast_ref() {
-            let left = self.execute_expr(left);
-            let right = self.execute_expr(right);
+            // The right side of `&&` and `||` may not be executed at all
+            if let TokenKind::DblAmp | TokenKind::DblBar = op.kind() {
+                return self.execute_logical(left.as_ref(), op, right.as_ref());
+            }
+
+            let left = self.execute_expr(left.as_ref())?;
+
+            if let (TokenKind::Dot, Some(module)) = (op.kind(), left.downcast_ref::<Module>()) {
+                return self.execute_expr(Self::member_expr(module, right.as_ref())?.as_ref());
+            }
+
+            let right = self.execute_expr(right.as_ref())?;
+
+            if let TokenKind::Dot = op.kind() {
+                return Self::execute_compose(left.as_ref(), right.as_ref());
+            }
 
             if let Some(l_func) = left.downcast_ref::<Func>() {
                 // right is a function
                 if let Some(r_func) = right.downcast_ref::<Func>() {
                     if l_func.arity() == r_func.arity() {
-                        let mut new_expr = r_func.expr().to_owned();
+                        let mut new_expr = r_func.expr().clone_expr();
                         Self::substitute_symbols(
-                            &mut new_expr, 
-                            &r_func.args().iter().map(|s| s.as_str()).collect::<Vec<_>>()[..], 
+                            &mut new_expr,
+                            &r_func.args().iter().map(|s| s.as_str()).collect::<Vec<_>>()[..],
                             l_func.args()
-                        );
+                        )?;
 
-                        return Box::new(Func::new(
+                        return Ok(Box::new(Func::new(
                             Rc::clone(l_func.env()),
                             l_func.args(),
                             Box::new(Binary(
-                                Box::new(Group(l_func.expr().to_owned())),
+                                Box::new(Group(l_func.expr().clone_expr())),
                                 op.to_owned(),
                                 Box::new(Group(new_expr))
                             )),
-                            &RefCell::borrow(&self.env).get_set("Univ").unwrap() // later do some math stuff here i guess
-                        ))
+                            &RefCell::borrow(&self.env).get_set("Univ").unwrap(), // later do some math stuff here i guess
+                            &self.settings
+                        )))
                     } else {
-                        panic!("Function shorthand can only be used with functions with the same arity.")
+                        return Self::error("Function shorthand can only be used with functions with the same arity.")
                     }
                 }
 
                 // right is not a function
-                return Box::new(Func::new(
+                return Ok(Box::new(Func::new(
                     Rc::clone(l_func.env()),
                     l_func.args(),
                     Box::new(Binary(
-                        Box::new(Group(l_func.expr().to_owned())),
+                        Box::new(Group(l_func.expr().clone_expr())),
                         op.to_owned(),
                         Box::new(Literal(right))
                     )),
-                    &RefCell::borrow(&self.env).get_set("Univ").unwrap() // later do some math stuff here i guess
-                ))
+                    &RefCell::borrow(&self.env).get_set("Univ").unwrap(), // later do some math stuff here i guess
+                    &self.settings
+                )))
             } else if let Some(r_func) = right.downcast_ref::<Func>() {
                 // left is not a function
-                return Box::new(Func::new(
+                return Ok(Box::new(Func::new(
                     Rc::clone(r_func.env()),
                     r_func.args(),
                     Box::new(Binary(
                         Box::new(Literal(left)),
                         op.to_owned(),
-                        Box::new(Group(r_func.expr().to_owned()))
+                        Box::new(Group(r_func.expr().clone_expr()))
                     )),
-                    &RefCell::borrow(&self.env).get_set("Univ").unwrap() // later do some math stuff here i guess
-                ))
+                    &RefCell::borrow(&self.env).get_set("Univ").unwrap(), // later do some math stuff here i guess
+                    &self.settings
+                )))
+            }
+
+            // Set operators bind looser than `+` and `-`, so an expression like `1 + {2} | {3}` ends up adding a number and a set.
+            // Adding to a string is still concatenation, though
+            let concatenates = op.kind() == &TokenKind::Plus && (left.is_str() || right.is_str());
+
+            if let (TokenKind::Plus | TokenKind::Minus | TokenKind::Star | TokenKind::Slash, false) = (op.kind(), concatenates) {
+                if let Some(set) = [&left, &right].into_iter().find(|value| value.is_set()) {
+                    let hint = match op.kind() {
+                        TokenKind::Plus => ", use '|' for a union",
+                        TokenKind::Minus => ", use '\\' for a difference",
+                        _ => ""
+                    };
+
+                    return Self::error(format!("Cannot apply arithmetic operator '{}' to the set '{set}'{hint}", op.lexeme()))
+                }
             }
 
             match op.kind() {
-                &TokenKind::Plus    => Self::execute_sum(&left, &right),
-                &TokenKind::Minus   => Self::execute_diff(&left, &right),
-                &TokenKind::Star    => Self::execute_prod(&left, &right),
-                &TokenKind::Slash   => Self::execute_quot(&left, &right),
-                &TokenKind::Caret   => Self::execute_power(&left, &right),
-                _ => todo!()
+                &TokenKind::Plus    => Self::execute_sum(left.as_ref(), right.as_ref()),
+                &TokenKind::Minus   => Self::execute_diff(left.as_ref(), right.as_ref()),
+                &TokenKind::Star    => Self::execute_prod(left.as_ref(), right.as_ref()),
+                &TokenKind::Slash   => Self::execute_quot(left.as_ref(), right.as_ref()),
+                &TokenKind::Caret   => self.execute_power(left.as_ref(), right.as_ref()),
+                &TokenKind::DblEq       |
+                &TokenKind::BangEq      |
+                &TokenKind::Less        |
+                &TokenKind::Greater     |
+                &TokenKind::LessEq      |
+                &TokenKind::GreaterEq   => Ok(Box::new(self.execute_comparison(left.as_ref(), op, right.as_ref())?)),
+                &TokenKind::Bar         |
+                &TokenKind::Amp         |
+                &TokenKind::BackSlash   |
+                &TokenKind::Tilde   => self.execute_set_op(left.as_ref(), op, right.as_ref()),
+                &TokenKind::EqColon         |
+                &TokenKind::LessColon       |
+                &TokenKind::GreaterColon    |
+                &TokenKind::LessEqColon     |
+                &TokenKind::GreaterEqColon  => Ok(Box::new(Self::execute_set_relation(left.as_ref(), op, right.as_ref())?)),
+                _ => Self::error(format!("Unknown binary operator '{}'", op.lexeme()))
+            }
+        } else if let Some(Chain(first, links)) = expr.downcast_ref() {
+            // Each operand is executed once, and only until a comparison fails
+            let mut left = self.execute_expr(first.as_ref())?;
+
+            for (op, right) in links {
+                let right = self.execute_expr(right.as_ref())?;
+
+                if !self.execute_comparison(left.as_ref(), op, right.as_ref())? {
+                    return Ok(Box::new(false));
+                }
+
+                left = right;
             }
+
+            Ok(Box::new(true))
         } else if let Some(expr::Tuple(exprs)) = expr.downcast_ref() {
-            Box::new(Tuple(exprs
+            Ok(Box::new(Tuple(exprs
                 .iter()
-                .map(|expr| self.execute_expr(expr))
-                .collect::<Vec<Box<dyn Val>>>()))
+                .map(|expr| self.execute_expr(expr.as_ref()))
+                .collect::<error::Result<Vec<Box<dyn Val>>>>()?)))
+        } else if let Some(expr::Matrix(rows)) = expr.downcast_ref() {
+            Ok(Box::new(Matrix::from_rows(rows
+                .iter()
+                .map(|row| row
+                    .iter()
+                    .map(|expr| self.execute_expr(expr.as_ref()))
+                    .collect())
+                .collect::<error::Result<_>>()?)))
         } else if let Some(expr::Set(values)) = expr.downcast_ref() {
             self.execute_set(values)
+        } else if let Some(SetBuilder(Symbol(name), source, cond)) = expr.downcast_ref() {
+            self.execute_set_builder(name, source.as_ref(), cond.as_deref())
+        } else if let Some(Comprehension(output, binders, cond)) = expr.downcast_ref() {
+            self.execute_comprehension(output.as_ref(), binders, cond.as_deref())
+        } else if let Some(TypeExpr(value, typeset)) = expr.downcast_ref() {
+            self.execute_cast(value.as_ref(), typeset.as_ref())
+        } else if let Some(BlockExpr(stmts)) = expr.downcast_ref() {
+            self.execute_block(stmts)
         } else if let Some(func) = expr.downcast_ref::<expr::Func>() {
-            Box::new(Func::from_func_expr(func, Rc::clone(&self.env)))
+            Ok(Box::new(Func::from_func_expr(func, Rc::clone(&self.env), &self.settings)?))
         } else if let Some(Call(func_expr, arg_exprs)) = expr.downcast_ref() {
-            let func_value = self.execute_expr(func_expr);
+            let func_value = self.execute_expr(func_expr.as_ref())?;
+            let args = arg_exprs
+                .iter()
+                .map(|arg| arg
+                    .as_ref()
+                    .map(|actual| self.execute_expr(actual.as_ref()))
+                    .transpose())
+                .collect::<error::Result<Vec<_>>>()?;
 
             if let Some(func) = func_value.downcast_ref::<Func>() {
-                let args = arg_exprs
-                    .iter()
-                    .map(|arg| if let Some(actual) = arg {
-                        Some(self.execute_expr(actual))
-                    } else {
-                        None
-                    })
-                    .collect::<Vec<_>>();
-
                 func.call(&args)
+            } else if let Some(native) = func_value.downcast_ref::<NativeFunc>() {
+                match native.call(&args) {
+                    Err(Error::Runtime(RuntimeError::Approximated(approx))) => self.approximate(format_args!("{expr}"), approx),
+                    result => result
+                }
             } else {
-                panic!("'{func_value}' is not callable")
+                Self::error(format!("'{func_value}' is not callable"))
             }
         } else {
-            todo!()
+            // Assignments and function types are statements, even when written in parentheses
+            Self::error(format!("'{expr}' does not have a value"))
         }
     }
 
     /// Is similar to [`Interpreter::execute_expr`], but doesn't actually execute any expression, but instead just replaces all symbols that aren't in the given `symbols` slice with their actual values.
-    pub fn curry_expr<'a>(&mut self, expr: &'a Box<dyn Expr>, symbols: &[&str]) -> Box<dyn Expr> {
-        if let Some(Literal(lit)) = expr.downcast_ref() {
-            expr.to_owned()
+    pub fn curry_expr(&mut self, expr: &dyn Expr, symbols: &[&str]) -> error::Result<Box<dyn Expr>> {
+        if expr.downcast_ref::<Literal>().is_some() {
+            Ok(expr.clone_expr())
         } else if let Some(Symbol(name)) = expr.downcast_ref() {
             if let Some(SymStore::Value(value)) = RefCell::borrow(&self.env).get(name) {
                 if !symbols.contains(&name.as_str()) {
-                    Box::new(Literal(value.clone()))
+                    Ok(Box::new(Literal(value.clone())))
                 } else {
-                    expr.clone()
+                    Ok(expr.clone_expr())
                 }
             } else if let Some(SymStore::Type(_)) = RefCell::borrow(&self.env).get(name) {
-                expr.clone()
+                Ok(expr.clone_expr())
             } else {
-                panic!("Variable '{name}' is not defined")
+                Self::error(format!("Variable '{name}' is not defined"))
             }
         } else if let Some(Group(expr)) = expr.downcast_ref::<Group>() {
-            Box::new(Group(self.curry_expr(expr, symbols)))
+            Ok(Box::new(Group(self.curry_expr(expr.as_ref(), symbols)?)))
         } else if let Some(Unary(op, right)) = expr.downcast_ref() {
-            Box::new(Unary(op.to_owned(), self.curry_expr(right, symbols)))
+            Ok(Box::new(Unary(op.to_owned(), self.curry_expr(right.as_ref(), symbols)?)))
+        } else if let Some(Postfix(left, op)) = expr.downcast_ref() {
+            Ok(Box::new(Postfix(self.curry_expr(left.as_ref(), symbols)?, op.to_owned())))
+        } else if let Some(Index(container, index)) = expr.downcast_ref() {
+            Ok(Box::new(Index(self.curry_expr(container.as_ref(), symbols)?, self.curry_expr(index.as_ref(), symbols)?)))
+        } else if let Some(Slice(container, start, end)) = expr.downcast_ref() {
+            Ok(Box::new(Slice(self.curry_expr(container.as_ref(), symbols)?, self.curry_expr(start.as_ref(), symbols)?, self.curry_expr(end.as_ref(), symbols)?)))
         } else if let Some(Binary(left, op, right)) = expr.downcast_ref() {
-            Box::new(Binary(self.curry_expr(left, symbols), op.to_owned(), self.curry_expr(right, symbols)))
+            let left = self.curry_expr(left.as_ref(), symbols)?;
+
+            // The name after a module only means something inside that module, so it is looked up right away
+            if let (TokenKind::Dot, Some(Literal(value))) = (op.kind(), left.downcast_ref::<Literal>()) {
+                if let Some(module) = value.downcast_ref::<Module>() {
+                    return self.curry_expr(Self::member_expr(module, right.as_ref())?.as_ref(), symbols);
+                }
+            }
+
+            Ok(Box::new(Binary(left, op.to_owned(), self.curry_expr(right.as_ref(), symbols)?)))
+        } else if let Some(Chain(first, links)) = expr.downcast_ref() {
+            Ok(Box::new(Chain(self.curry_expr(first.as_ref(), symbols)?, links
+                .iter()
+                .map(|(op, right)| Ok((op.to_owned(), self.curry_expr(right.as_ref(), symbols)?)))
+                .collect::<error::Result<_>>()?)))
         } else if let Some(expr::Tuple(exprs)) = expr.downcast_ref() {
-            Box::new(expr::Tuple(exprs
+            Ok(Box::new(expr::Tuple(exprs
+                .iter()
+                .map(|expr| self.curry_expr(expr.as_ref(), symbols))
+                .collect::<error::Result<Vec<Box<dyn Expr>>>>()?)))
+        } else if let Some(expr::Matrix(rows)) = expr.downcast_ref() {
+            Ok(Box::new(expr::Matrix(rows
                 .iter()
-                .map(|expr| self.curry_expr(expr, symbols))
-                .collect::<Vec<Box<dyn Expr>>>()))
+                .map(|row| row
+                    .iter()
+                    .map(|expr| self.curry_expr(expr.as_ref(), symbols))
+                    .collect())
+                .collect::<error::Result<_>>()?)))
         } else if let Some(expr::Set(values)) = expr.downcast_ref() {
-            Box::new(expr::Set(values.iter().map(|x| self.curry_expr(x, symbols)).collect()))
-        } else if let Some(expr::Func(args, result)) = expr.downcast_ref::<expr::Func>() {
-            todo!() // this may be a bit more compelx
-
-            // Box::new(Func::from_func_expr(func, Rc::clone(&self.env), &mut self.set_pool))
+            Ok(Box::new(expr::Set(values.iter().map(|x| self.curry_expr(x.as_ref(), symbols)).collect::<error::Result<_>>()?)))
+        } else if let Some(SetBuilder(bound, source, cond)) = expr.downcast_ref() {
+            // The bound variable is only given a value while building the set
+            let inner_symbols = symbols.iter().copied().chain([bound.0.as_str()]).collect::<Vec<_>>();
+
+            Ok(Box::new(SetBuilder(
+                bound.to_owned(),
+                self.curry_expr(source.as_ref(), symbols)?,
+                cond.as_ref().map(|cond| self.curry_expr(cond.as_ref(), &inner_symbols)).transpose()?
+            )))
+        } else if let Some(Comprehension(output, binders, cond)) = expr.downcast_ref() {
+            // The bound variables are only given values while building the set
+            let inner_symbols = symbols.iter().copied()
+                .chain(binders.iter().map(|(bound, _)| bound.0.as_str()))
+                .collect::<Vec<_>>();
+
+            Ok(Box::new(Comprehension(
+                self.curry_expr(output.as_ref(), &inner_symbols)?,
+                binders.iter().map(|(bound, source)| Ok((bound.to_owned(), self.curry_expr(source.as_ref(), symbols)?))).collect::<error::Result<_>>()?,
+                cond.as_ref().map(|cond| self.curry_expr(cond.as_ref(), &inner_symbols)).transpose()?
+            )))
+        } else if let Some(expr::Func(args, inner, arg_types)) = expr.downcast_ref() {
+            // Arg types are found outside the function, but its args shadow anything with the same name in its body
+            let inner_symbols = symbols.iter().copied().chain(args.iter().map(|arg| arg.0.as_str())).collect::<Vec<_>>();
+
+            Ok(Box::new(expr::Func(
+                args.to_owned(),
+                self.curry_expr(inner.as_ref(), &inner_symbols)?,
+                arg_types.iter().map(|typeset| typeset.as_ref().map(|typeset| self.curry_expr(typeset.as_ref(), symbols)).transpose()).collect::<error::Result<_>>()?
+            )))
+        } else if let Some(TypeExpr(value, typeset)) = expr.downcast_ref() {
+            Ok(Box::new(TypeExpr(self.curry_expr(value.as_ref(), symbols)?, self.curry_expr(typeset.as_ref(), symbols)?)))
+        } else if let Some(FuncTypeExpr(func, arg_types, codom)) = expr.downcast_ref() {
+            // The function is a name being declared, not a variable
+            Ok(Box::new(FuncTypeExpr(
+                func.to_owned(),
+                arg_types.iter().map(|typeset| self.curry_expr(typeset.as_ref(), symbols)).collect::<error::Result<_>>()?,
+                self.curry_expr(codom.as_ref(), symbols)?
+            )))
         } else if let Some(Call(func_expr, arg_exprs)) = expr.downcast_ref() {
-            let curry_func_expr = self.curry_expr(func_expr, symbols);
+            let curry_func_expr = self.curry_expr(func_expr.as_ref(), symbols)?;
             let curry_args = arg_exprs
                 .iter()
-                .map(|a| if let Some(actual) = a {
-                    Some(self.curry_expr(actual, symbols))
+                .map(|a| a
+                    .as_ref()
+                    .map(|actual| self.curry_expr(actual.as_ref(), symbols))
+                    .transpose())
+                .collect::<error::Result<_>>()?;
+
+            Ok(Box::new(Call(curry_func_expr, curry_args)))
+        } else if let Some(BlockExpr(stmts)) = expr.downcast_ref() {
+            Ok(Box::new(BlockExpr(self.curry_block(stmts, symbols)?)))
+        } else {
+            Self::error(format!("'{expr}' cannot be used inside a function"))
+        }
+    }
+
+    /// Is [`Interpreter::curry_expr`] for the statements of a block, where each name the block defines is left as it is from then on.
+    fn curry_block(&mut self, stmts: &[Box<dyn Stmt>], symbols: &[&str]) -> error::Result<Vec<Box<dyn Stmt>>> {
+        let mut symbols = symbols.to_vec();
+        let mut curried = Vec::with_capacity(stmts.len());
+
+        for stmt in stmts {
+            if let Some(ExprStmt(expr, is_to_log)) = stmt.downcast_ref() {
+                let (expr, defined): (Box<dyn Expr>, Option<&str>) = if let Some(Assign(name, value)) = expr.downcast_ref() {
+                    (Box::new(Assign(name.to_owned(), self.curry_expr(value.as_ref(), &symbols)?)), Some(&name.0))
+                } else if let Some(TypedAssign(name, typeset, value)) = expr.downcast_ref() {
+                    (Box::new(TypedAssign(name.to_owned(), self.curry_expr(typeset.as_ref(), &symbols)?, self.curry_expr(value.as_ref(), &symbols)?)), Some(&name.0))
+                } else if let Some((name, typeset)) = self.declared_type(expr.as_ref(), &symbols) {
+                    // A declaration like `y : Int`, rather than a cast of a value
+                    (Box::new(TypeExpr(Box::new(Symbol(name.to_owned())), self.curry_expr(typeset, &symbols)?)), Some(name))
+                } else {
+                    (self.curry_expr(expr.as_ref(), &symbols)?, None)
+                };
+
+                curried.push(Box::new(ExprStmt(expr, *is_to_log)) as Box<dyn Stmt>);
+                symbols.extend(defined);
+            } else if let Some(Block(inner)) = stmt.downcast_ref() {
+                curried.push(Box::new(Block(self.curry_block(inner, &symbols)?)));
+            } else if let Some(Import(_, alias)) = stmt.downcast_ref() {
+                curried.push(stmt.to_owned());
+                symbols.push(alias);
+            } else {
+                return Self::error(format!("'{stmt}' cannot be used inside a function"))
+            }
+        }
+
+        Ok(curried)
+    }
+
+    /// Returns the name and type of `expr` if it declares the type of a name that has no value yet, like `y : Int`.
+    fn declared_type<'e>(&self, expr: &'e dyn Expr, symbols: &[&str]) -> Option<(&'e str, &'e dyn Expr)> {
+        match expr.downcast_ref() {
+            Some(TypeExpr(value, typeset)) => match value.downcast_ref() {
+                Some(Symbol(name)) if !symbols.contains(&name.as_str()) && !matches!(RefCell::borrow(&self.env).get(name), Some(SymStore::Value(_))) => Some((name, typeset.as_ref())),
+                _ => None
+            }
+            _ => None
+        }
+    }
+
+    /// Is [`Interpreter::substitute_symbols`] for the statements of a block, where each name the block defines shadows the symbol it would replace from then on.
+    fn substitute_in_block(stmts: &mut [Box<dyn Stmt>], find_args: &[&str], replace_with: &[String]) -> error::Result<()> {
+        let mut find_args = find_args.to_vec();
+        let mut replace_with = replace_with.to_vec();
+
+        for stmt in stmts {
+            let defined = if let Some(ExprStmt(expr, _)) = stmt.downcast_mut() {
+                if let Some(Assign(name, value)) = expr.downcast_mut() {
+                    Self::substitute_symbols(value, &find_args, &replace_with)?;
+                    Some(name.0.to_owned())
+                } else if let Some(TypedAssign(name, typeset, value)) = expr.downcast_mut() {
+                    Self::substitute_symbols(typeset, &find_args, &replace_with)?;
+                    Self::substitute_symbols(value, &find_args, &replace_with)?;
+                    Some(name.0.to_owned())
                 } else {
+                    Self::substitute_symbols(expr, &find_args, &replace_with)?;
                     None
-                })
-                .collect();
+                }
+            } else if let Some(Block(inner)) = stmt.downcast_mut() {
+                Self::substitute_in_block(inner, &find_args, &replace_with)?;
+                None
+            } else if let Some(Import(_, alias)) = stmt.downcast_ref() {
+                Some(alias.to_owned())
+            } else {
+                return Self::error(format!("'{stmt}' cannot be used inside a function"))
+            };
 
-            Box::new(Call(curry_func_expr, curry_args))
-        } else {
-            todo!()
+            if let Some(i) = defined.and_then(|name| find_args.iter().position(|arg| *arg == name)) {
+                find_args.remove(i);
+                replace_with.remove(i);
+            }
         }
+
+        Ok(())
     }
 
     /// Substitutes all instances of symbols in `find_args` with their corresponding symbol in `replace_with`.
     /// 
     /// Thus, `find_args.len() == replace_with.len()`.
-    fn substitute_symbols(expr: &mut Box<dyn Expr>, find_args: &[&str], replace_with: &[String]) {
-        if let Some(_) = expr.downcast_ref::<Literal>() {
-            ()
+    pub fn substitute_symbols(expr: &mut Box<dyn Expr>, find_args: &[&str], replace_with: &[String]) -> error::Result<()> {
+        if expr.downcast_ref::<Literal>().is_some() {
+            // Literals have no symbols to substitute
         } else if let Some(symbol) = expr.downcast_mut::<Symbol>() {
             if let Some(i) = find_args.iter().position(|name| name == &symbol.0) {
                 symbol.0 = replace_with[i].clone()
             }
         } else if let Some(Group(inner)) = expr.downcast_mut() {
-            Self::substitute_symbols(inner, find_args, replace_with);
+            Self::substitute_symbols(inner, find_args, replace_with)?;
         } else if let Some(Unary(_, operand)) = expr.downcast_mut() {
-            Self::substitute_symbols(operand, find_args, replace_with);
+            Self::substitute_symbols(operand, find_args, replace_with)?;
+        } else if let Some(Postfix(operand, _)) = expr.downcast_mut() {
+            Self::substitute_symbols(operand, find_args, replace_with)?;
+        } else if let Some(Index(container, index)) = expr.downcast_mut() {
+            Self::substitute_symbols(container, find_args, replace_with)?;
+            Self::substitute_symbols(index, find_args, replace_with)?;
+        } else if let Some(Slice(container, start, end)) = expr.downcast_mut() {
+            Self::substitute_symbols(container, find_args, replace_with)?;
+            Self::substitute_symbols(start, find_args, replace_with)?;
+            Self::substitute_symbols(end, find_args, replace_with)?;
+        } else if is_member(expr.as_ref()) {
+            // The name after the module's isn't a variable here, so only the module side is substituted
+            if let Some(Binary(left, _, _)) = expr.downcast_mut() {
+                Self::substitute_symbols(left, find_args, replace_with)?;
+            }
         } else if let Some(Binary(left, _, right)) = expr.downcast_mut() {
-            Self::substitute_symbols(left, find_args, replace_with);
-            Self::substitute_symbols(right, find_args, replace_with);
+            Self::substitute_symbols(left, find_args, replace_with)?;
+            Self::substitute_symbols(right, find_args, replace_with)?;
+        } else if let Some(Chain(first, links)) = expr.downcast_mut() {
+            Self::substitute_symbols(first, find_args, replace_with)?;
+
+            for (_, right) in links {
+                Self::substitute_symbols(right, find_args, replace_with)?;
+            }
         } else if let Some(Call(func, args)) = expr.downcast_mut() {
-            Self::substitute_symbols(func, find_args, replace_with);
+            Self::substitute_symbols(func, find_args, replace_with)?;
 
-            for arg in args.iter_mut() {
-                if let Some(actual) = arg {
-                    Self::substitute_symbols(actual, find_args, replace_with);
-                }
+            for actual in args.iter_mut().flatten() {
+                Self::substitute_symbols(actual, find_args, replace_with)?;
             }
-        } else if let Some(expr::Func(args, inner)) = expr.downcast_mut() {
+        } else if let Some(expr::Func(args, inner, arg_types)) = expr.downcast_mut() {
+            // Arg types are found outside the function, so its args don't shadow anything in them
+            for typeset in arg_types.iter_mut().flatten() {
+                Self::substitute_symbols(typeset, find_args, replace_with)?;
+            }
+
             let mut new_find_args: Vec<&str> = Vec::with_capacity(find_args.len());
             let mut new_replace_with = Vec::with_capacity(replace_with.len());
             
             for (i, arg) in find_args.iter().enumerate() {
-                if let Some(_) = args.iter().position(|a| a.0 == *arg) {
-                    ()
-                } else {
+                if !args.iter().any(|a| a.0 == *arg) {
                     new_find_args.push(arg);
                     new_replace_with.push(replace_with[i].clone())
                 }
             }
 
-            Self::substitute_symbols(inner, &new_find_args[..], &new_replace_with);
+            Self::substitute_symbols(inner, &new_find_args[..], &new_replace_with)?;
         } else if let Some(expr::Tuple(exprs)) = expr.downcast_mut() {
             for x in exprs {
-                Self::substitute_symbols(x, find_args, replace_with);
+                Self::substitute_symbols(x, find_args, replace_with)?;
             }
         } else if let Some(Matrix(mat)) = expr.downcast_mut() {
             for row in mat {
                 for x in row {
-                    Self::substitute_symbols(x, find_args, replace_with);
+                    Self::substitute_symbols(x, find_args, replace_with)?;
+                }
+            }
+        } else if let Some(expr::Set(exprs)) = expr.downcast_mut() {
+            for x in exprs {
+                Self::substitute_symbols(x, find_args, replace_with)?;
+            }
+        } else if let Some(SetBuilder(bound, source, cond)) = expr.downcast_mut() {
+            Self::substitute_symbols(source, find_args, replace_with)?;
+
+            // The bound variable shadows any symbol with the same name
+            if let Some(cond) = cond {
+                if let Some(i) = find_args.iter().position(|name| *name == bound.0) {
+                    let mut new_find_args = find_args.to_vec();
+                    let mut new_replace_with = replace_with.to_vec();
+
+                    new_find_args.remove(i);
+                    new_replace_with.remove(i);
+
+                    Self::substitute_symbols(cond, &new_find_args, &new_replace_with)?;
+                } else {
+                    Self::substitute_symbols(cond, find_args, replace_with)?;
                 }
             }
-        } else if let Some(_) = expr.downcast_mut::<expr::Set>() {
-            todo!() // may get a bit weird?
+        } else if let Some(Comprehension(output, binders, cond)) = expr.downcast_mut() {
+            for (_, source) in binders.iter_mut() {
+                Self::substitute_symbols(source, find_args, replace_with)?;
+            }
+
+            // The bound variables shadow any symbols with the same names
+            let (new_find_args, new_replace_with): (Vec<&str>, Vec<String>) = find_args
+                .iter()
+                .zip(replace_with)
+                .filter(|(name, _)| !binders.iter().any(|(bound, _)| bound.0 == **name))
+                .map(|(name, replacement)| (*name, replacement.to_owned()))
+                .unzip();
+
+            Self::substitute_symbols(output, &new_find_args, &new_replace_with)?;
+
+            if let Some(cond) = cond {
+                Self::substitute_symbols(cond, &new_find_args, &new_replace_with)?;
+            }
+        } else if let Some(TypeExpr(value, typeset)) = expr.downcast_mut() {
+            Self::substitute_symbols(value, find_args, replace_with)?;
+            Self::substitute_symbols(typeset, find_args, replace_with)?;
+        } else if let Some(FuncTypeExpr(_, arg_types, codom)) = expr.downcast_mut() {
+            // The function is a name being declared, not a variable
+            for typeset in arg_types {
+                Self::substitute_symbols(typeset, find_args, replace_with)?;
+            }
+
+            Self::substitute_symbols(codom, find_args, replace_with)?;
+        } else if let Some(BlockExpr(stmts)) = expr.downcast_mut() {
+            Self::substitute_in_block(stmts, find_args, replace_with)?;
         } else {
-            todo!()
+            return Self::error(format!("'{expr}' cannot be used inside a function"))
         }
+
+        Ok(())
     }
 
-    fn execute_literal(lit: &Box<dyn Val>) -> Box<dyn Val> {
+    fn execute_literal(lit: &dyn Val) -> Box<dyn Val> {
         if let Ok(bigint) = lit.downcast::<BigInt>() {
             bigint
         } else if let Ok(bigrat) = lit.downcast::<BigRational>() {
@@ -390,321 +999,406 @@ impl Interpreter {
             string
         } else if let Ok(bool) = lit.downcast::<bool>() {
             bool
+        } else if let Ok(unit) = lit.downcast::<Unit>() {
+            unit
         } else {
-            todo!()
+            lit.clone_box() // values with no literal syntax, like a function inside a composed one
         }
     }
 
-    fn execute_neg(right: &Box<dyn Val>) -> Box<dyn Val> {
+    /// Executes `n!` for a natural number `n`, whatever its representation, so `0! = 1` and `(4/2)! = 2`.
+    fn execute_factorial(left: &dyn Val) -> error::Result<Box<dyn Val>> {
+        match left.as_bigint() {
+            Some(n) if n.sign() != Sign::Minus => {
+                let mut product = BigInt::one();
+                let mut i = BigInt::one();
+
+                while i <= n {
+                    product *= &i;
+                    i += 1;
+                }
+
+                Ok(Box::new(product))
+            }
+            _ => Self::error(format!("Cannot take the factorial of '{left}', because it is not a natural number"))
+        }
+    }
+
+    /// `+x` leaves numbers unchanged, keeping their representation, and maps over tuples and matrices like `-x`.
+    fn execute_pos(right: &dyn Val) -> error::Result<Box<dyn Val>> {
+        if let Some(Tuple(elements)) = right.downcast_ref() {
+            return Ok(Box::new(Tuple(elements.iter().map(|element| Self::execute_pos(element.as_ref())).collect::<error::Result<_>>()?)));
+        } else if let Some(mat) = right.downcast_ref::<Matrix>() {
+            return Ok(Box::new(mat.try_map(Self::execute_pos)?));
+        }
+
+        // Bools become 0 and 1, as in any other arithmetic
+        match NumVal::from_val(right) {
+            Some(NumVal::Int(bigint)) => Ok(Box::new(bigint)),
+            Some(NumVal::Real(bigrat)) => Ok(Box::new(bigrat)),
+            Some(NumVal::Complex(complex)) => Ok(Box::new(complex)),
+            None => Self::operator_error("+", &[right])
+        }
+    }
+
+    fn execute_neg(right: &dyn Val) -> error::Result<Box<dyn Val>> {
+        // Tuples and Matrices negate each element
+        if let Some(Tuple(elements)) = right.downcast_ref() {
+            return Ok(Box::new(Tuple(elements.iter().map(|element| Self::execute_neg(element.as_ref())).collect::<error::Result<_>>()?)));
+        } else if let Some(mat) = right.downcast_ref::<Matrix>() {
+            return Ok(Box::new(mat.try_map(Self::execute_neg)?));
+        }
+
         // Numbers -x
-        if let Some(bigint) = right.downcast_ref::<BigInt>() {
-            Box::new(-bigint)
-        } else if let Some(bigrat) = right.downcast_ref::<BigRational>() {
-            Box::new(-bigrat)
-        } else if let Some(complex) = right.downcast_ref::<Complex<BigRational>>() {
-            Box::new(-complex)
-        } else if let Some(&bool) = right.downcast_ref::<bool>() {
-            Box::new(bool)
+        match NumVal::from_val(right) {
+            Some(NumVal::Int(bigint)) => Ok(Box::new(-bigint)),
+            Some(NumVal::Real(bigrat)) => Ok(Box::new(-bigrat)),
+            Some(NumVal::Complex(complex)) => Ok(Box::new(-complex)),
+            None => Self::operator_error("-", &[right])
+        }
+    }
+
+    /// `~` is the complement of a set, and the conjugate of a number.
+    fn execute_tilde(&mut self, right: &dyn Val) -> error::Result<Box<dyn Val>> {
+        if let Some(set) = right.downcast_ref::<Rc<CanonSet>>() {
+            return Ok(Box::new(self.set_pool.intern(&canon(Rc::new(CanonSet::Complement(Rc::clone(set)))))));
+        }
+
+        match NumVal::from_val(right) {
+            Some(NumVal::Int(bigint)) => Ok(Box::new(bigint)),
+            Some(NumVal::Real(bigrat)) => Ok(Box::new(bigrat)),
+            Some(NumVal::Complex(complex)) => Ok(Box::new(complex.conj())),
+            None => Self::operator_error("~", &[right])
+        }
+    }
+
+    /// `#` is the number of elements of a finite set (or a predicate set over one), and otherwise just whether it is countable.
+    fn execute_cardinality(right: &dyn Val) -> error::Result<Box<dyn Val>> {
+        let Some(set) = right.downcast_ref::<Rc<CanonSet>>() else {
+            return Self::error(format!("cannot take cardinality of '{right}'"))
+        };
+
+        let members = match set.as_ref() {
+            CanonSet::Finite(set) => Some(set.len()),
+            CanonSet::Predicate(set) => set.members()?.map(|members| members.len()),
+            _ => None
+        };
+
+        if let Some(len) = members {
+            return Ok(Box::new(BigInt::from(len)))
+        }
+
+        let cardinality = match set.as_ref() {
+            // Any number of the domain's elements could satisfy the predicate
+            CanonSet::Predicate(_) if set.is_countable() => Cardinality::AtMostCountable,
+            CanonSet::Predicate(_) => Cardinality::Unknown,
+
+            _ if set.is_countable() => Cardinality::Countable,
+            _ => Cardinality::Uncountable
+        };
+
+        Ok(Box::new(cardinality))
+    }
+
+    /// Returns the element of a tuple, or the character of a string, at a zero-based index.
+    fn execute_index(container: &dyn Val, index: &dyn Val) -> error::Result<Box<dyn Val>> {
+        let Some(i) = index.as_bigint() else {
+            return Self::error(format!("Index '{index}' is not a natural number"))
+        };
+
+        let (element, len): (Option<Box<dyn Val>>, usize) = if let Some(Tuple(elements)) = container.downcast_ref() {
+            (i.to_usize().and_then(|i| elements.get(i)).cloned(), elements.len())
+        } else if let Some(string) = container.downcast_ref::<String>() {
+            // A character is a string of one character, as there is no separate value for them yet
+            (i.to_usize().and_then(|i| string.chars().nth(i)).map(|ch| Box::new(ch.to_string()) as Box<dyn Val>), string.chars().count())
+        } else {
+            return Self::error(format!("Cannot index '{container}', because it is not a tuple or a string"))
+        };
+
+        match element {
+            Some(element) => Ok(element),
+            None => Self::error(format!("Index {i} is out of range for '{container}', which has a length of {len}"))
+        }
+    }
+
+    /// Returns the elements from `start` up to, but not including, `end`. Bounds past the end are an error rather than being clamped.
+    fn execute_slice(container: &dyn Val, start: &dyn Val, end: &dyn Val) -> error::Result<Box<dyn Val>> {
+        let (Some(start), Some(end)) = (start.as_bigint(), end.as_bigint()) else {
+            return Self::error(format!("Slice bounds '{start}' and '{end}' are not both natural numbers"))
+        };
+
+        let len = if let Some(Tuple(elements)) = container.downcast_ref() {
+            elements.len()
+        } else if let Some(string) = container.downcast_ref::<String>() {
+            string.chars().count()
+        } else {
+            return Self::error(format!("Cannot slice '{container}', because it is not a tuple or a string"))
+        };
+
+        let (Some(from), Some(to)) = (start.to_usize(), end.to_usize()) else {
+            return Self::error(format!("Slice {start}..{end} has a negative bound"))
+        };
+
+        if from > to {
+            return Self::error(format!("Slice {start}..{end} is reversed, as its start is after its end"))
+        } else if to > len {
+            return Self::error(format!("Slice {start}..{end} is out of range for '{container}', which has a length of {len} (bounds are not clamped)"))
+        }
+
+        if let Some(Tuple(elements)) = container.downcast_ref() {
+            Ok(Box::new(Tuple(elements[from..to].to_vec())))
+        } else if let Some(string) = container.downcast_ref::<String>() {
+            Ok(Box::new(string.chars().skip(from).take(to - from).collect::<String>()))
         } else {
-            panic!("Cannot apply unary operator '-'");
+            unreachable!()
         }
     }
 
-    fn execute_sum(left: &Box<dyn Val>, right: &Box<dyn Val>) -> Box<dyn Val> {
+    fn execute_sum(left: &dyn Val, right: &dyn Val) -> error::Result<Box<dyn Val>> {
         // String + _
         if let Ok(l_str) = left.downcast::<String>() {
-            Box::new(*l_str + &right.display())
+            Ok(Box::new(*l_str + &right.display()))
         // _ + String
         } else if let Ok(r_str) = right.downcast::<String>() {
-            Box::new(left.display() + &*r_str)
-        // BigInt + _
-        } else if let Ok(l_bigint) = left.downcast::<BigInt>() {
-            // Adding BigInt
-            if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                Box::new(*l_bigint + *r_bigint)
-            // Adding BigRational
-            } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                Box::new(BigRational::from(*l_bigint) + *r_bigrat)
-            // Adding Complex
-            } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                Box::new(Complex::<BigRational>::from(BigRational::from(*l_bigint)) + *r_complex)
-            } else if let Ok(r_bool) = right.downcast::<bool>() {
-                Box::new(*l_bigint + *r_bool as u8)
-            } else {
-                panic!("Cannot apply binary operator '+'")
-            }
-        // BigRational + _
-        } else if let Ok(l_bigrat) = left.downcast::<BigRational>() {
-            // Adding BigInt
-            if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                Box::new(*l_bigrat + *r_bigint)
-            // Adding BigRational
-            } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                Box::new(*l_bigrat + *r_bigrat)
-            // Adding Complex
-            } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                Box::new(Complex::<BigRational>::from(*l_bigrat) + *r_complex)
-            } else if let Ok(r_bool) = right.downcast::<bool>() {
-                Box::new(*l_bigrat + BigInt::from(*r_bool as u8))
-            } else {
-                panic!("Cannot apply binary operator '+'")
-            }
-        // Complex + _
-        } else if let Ok(l_complex) = left.downcast::<Complex<BigRational>>() {
-            // Adding BigInt
-            if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                Box::new(*l_complex + Complex::<BigRational>::from(BigRational::from(*r_bigint)))
-            // Adding BigRational
-            } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                Box::new(*l_complex + *r_bigrat)
-            // Adding Complex
-            } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                Box::new(*l_complex + *r_complex)
-            } else if let Ok(r_bool) = right.downcast::<bool>() {
-                Box::new(*l_complex + BigRational::from(BigInt::from(*r_bool as u8)))
-            } else {
-                panic!("Cannot apply binary operator '+'")
-            }
-        } else if let Ok(l_bool) = left.downcast::<bool>() {
-            // Adding BigInt
-            if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                Box::new(*l_bool as u8 + *r_bigint)
-            // Adding BigRational
-            } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                Box::new(BigRational::from(BigInt::from(*l_bool as u8)) + *r_bigrat)
-            // Adding Complex
-            } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                Box::new(Complex::<BigRational>::from(BigRational::from(BigInt::from(*l_bool as u8))) + *r_complex)
-            } else if let Ok(r_bool) = right.downcast::<bool>() {
-                Box::new(*l_bool ^ *r_bool)
-            } else {
-                panic!("Cannot apply binary operator '+'")
+            Ok(Box::new(left.display() + &*r_str))
+        // Matrix + Matrix
+        } else if let (Some(l_mat), Some(r_mat)) = (left.downcast_ref::<Matrix>(), right.downcast_ref::<Matrix>()) {
+            Self::execute_mat_sum(l_mat, r_mat)
+        // Bools are added as 0 and 1, even with each other
+        } else if let Some((l_num, r_num, _)) = coerce_pair(left, right) {
+            match (l_num, r_num) {
+                (NumVal::Int(l), NumVal::Int(r)) => Ok(Box::new(l + r)),
+                (NumVal::Real(l), NumVal::Real(r)) => Ok(Box::new(l + r)),
+                (NumVal::Complex(l), NumVal::Complex(r)) => Ok(Box::new(l + r)),
+                _ => unreachable!()
             }
         } else {
-            panic!("Cannot apply binary operator '+'")
+            Self::operator_error("+", &[left, right])
         }
     }
 
-    fn execute_diff(left: &Box<dyn Val>, right: &Box<dyn Val>) -> Box<dyn Val> {
-        if let Ok(_) = left.downcast::<String>() {
-            panic!("Cannot subtract from a string")
-        } else if let Ok(_) = right.downcast::<String>() {
-            panic!("Cannot subtract a string")
-        } else if left.is_num() && right.is_num() {
-            let right = Self::execute_neg(right);
-
-            Self::execute_sum(left, &right)
-        } else {
-            todo!()
-        }
-    }
-
-    fn execute_prod(left: &Box<dyn Val>, right: &Box<dyn Val>) -> Box<dyn Val> {
-        if let Ok(_) = left.downcast::<String>() {
-            // Will have to check if the value belongs to Nat and then repeat the string if so
-            todo!()
-        } else if right.is_str() {
-            panic!("Cannot multiply by a string")
-        } else if let Ok(l_bigint) = left.downcast::<BigInt>() {
-            // Adding BigInt
-            if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                Box::new(*l_bigint * *r_bigint)
-            // Adding BigRational
-            } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                Box::new(BigRational::from(*l_bigint) * *r_bigrat)
-            // Adding Complex
-            } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                Box::new(Complex::<BigRational>::from(BigRational::from(*l_bigint)) * *r_complex)
-            // Adding Bool
-            } else if let Ok(r_bool) = right.downcast::<bool>() {
-                Box::new(*l_bigint * *r_bool as u8)
-            } else {
-                panic!("Cannot apply binary operator '*'")
-            }
-        // BigRational + _
-        } else if let Ok(l_bigrat) = left.downcast::<BigRational>() {
-            // Multiplying BigInt
-            if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                Box::new(*l_bigrat * *r_bigint)
-            // Multiplying BigRational
-            } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                Box::new(*l_bigrat * *r_bigrat)
-            // Multiplying Complex
-            } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                Box::new(Complex::<BigRational>::from(*l_bigrat) * *r_complex)
-            // Multiplying Bool
-            } else if let Ok(r_bool) = right.downcast::<bool>() {
-                Box::new(*l_bigrat * BigInt::from(*r_bool as u8))
-            } else {
-                panic!("Cannot apply binary operator '*'")
-            }
-        // Complex * _
-        } else if let Ok(l_complex) = left.downcast::<Complex<BigRational>>() {
-            // Multiplying BigInt
-            if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                Box::new(*l_complex * Complex::<BigRational>::from(BigRational::from(*r_bigint)))
-            // Multiplying BigRational
-            } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                Box::new(*l_complex * *r_bigrat)
-            // Multiplying Complex
-            } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                Box::new(*l_complex * *r_complex)
-            // Multiplying Bool
-            } else if let Ok(r_bool) = right.downcast::<bool>() {
-                Box::new(*l_complex * BigRational::from(BigInt::from(*r_bool as u8)))
-            } else {
-                panic!("Cannot apply binary operator '*'")
-            }
-        } else if let Ok(l_bool) = left.downcast::<bool>() {
-            // Multiplying BigInt
-            if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                Box::new(*l_bool as u8 * *r_bigint)
-            // Multiplying BigRational
-            } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                Box::new(BigRational::from(BigInt::from(*l_bool as u8)) * *r_bigrat)
-            // Multiplying Complex
-            } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                Box::new(Complex::<BigRational>::from(BigRational::from(BigInt::from(*l_bool as u8))) * *r_complex)
-            } else if let Ok(r_bool) = right.downcast::<bool>() {
-                Box::new(*l_bool & *r_bool)
-            } else {
-                panic!("Cannot apply binary operator '*'")
+    fn execute_diff(left: &dyn Val, right: &dyn Val) -> error::Result<Box<dyn Val>> {
+        if left.is_str() || right.is_str() {
+            Self::operator_error("-", &[left, right])
+        // A - B is A + (-B) for matrices
+        } else if left.is_mat() && right.is_mat() {
+            Self::execute_sum(left, Self::execute_neg(right)?.as_ref())
+        } else if let Some((l_num, r_num, _)) = coerce_pair(left, right) {
+            match (l_num, r_num) {
+                (NumVal::Int(l), NumVal::Int(r)) => Ok(Box::new(l - r)),
+                (NumVal::Real(l), NumVal::Real(r)) => Ok(Box::new(l - r)),
+                (NumVal::Complex(l), NumVal::Complex(r)) => Ok(Box::new(l - r)),
+                _ => unreachable!()
             }
+        // Tuples, and matrices with anything but a matrix, have no difference
         } else {
-            panic!("Cannot apply binary operator '*'")
+            Self::operator_error("-", &[left, right])
         }
     }
 
-    fn execute_quot(left: &Box<dyn Val>, right: &Box<dyn Val>) -> Box<dyn Val> {
-        if left.is_str() || right.is_str() {
-            panic!("Cannot apply binary operator '/' to text")
-        // BigInt / _
-        } else if let Ok(l_bigint) = left.downcast::<BigInt>() {
-            // Dividing BigInt
-            if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                Box::new(BigRational::new(*l_bigint, *r_bigint))
-            // Dividing BigRational
-            } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                Box::new(BigRational::from(*l_bigint) / *r_bigrat)
-            // Dividing Complex
-            } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                Box::new(Complex::<BigRational>::from(BigRational::from(*l_bigint)) / *r_complex)
-            // Cannot Divide by Bools
-            } else if let Ok(_) = right.downcast::<bool>() {
-                panic!("Cannot divide by a boolean")
-            } else {
-                panic!("Cannot apply binary operator '/'")
-            }
-        // BigRational / _
-        } else if let Ok(l_bigrat) = left.downcast::<BigRational>() {
-            // Dividing BigInt
-            if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                Box::new(*l_bigrat / *r_bigint)
-            // Dividing BigRational
-            } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                Box::new(*l_bigrat / *r_bigrat)
-            // Dividing Complex
-            } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                Box::new(Complex::<BigRational>::from(*l_bigrat) / *r_complex)
-            // Cannot Divide by Bools
-            } else if let Ok(_) = right.downcast::<bool>() {
-                panic!("Cannot divide by a boolean")
-            } else {
-                panic!("Cannot apply binary operator '/'")
-            }
-        // Complex / _
-        } else if let Ok(l_complex) = left.downcast::<Complex<BigRational>>() {
-            // Dividing BigInt
-            if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                Box::new(*l_complex / Complex::<BigRational>::from(BigRational::from(*r_bigint)))
-            // Dividing BigRational
-            } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                Box::new(*l_complex / *r_bigrat)
-            // Dividing Complex
-            } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                Box::new(*l_complex / *r_complex)
-            // Cannot Divide by Bools
-            } else if let Ok(_) = right.downcast::<bool>() {
-                panic!("Cannot divide by a boolean")
-            } else {
-                panic!("Cannot apply binary operator '/'")
-            }
-        // Cannot use division with booleans
-        } else if let Ok(_) = left.downcast::<bool>() {
-            panic!("Cannot use division with booleans")
-        } else {
-            panic!("Cannot apply binary operator '/'")
+    fn execute_mat_sum(left: &Matrix, right: &Matrix) -> error::Result<Box<dyn Val>> {
+        if left.dims() != right.dims() {
+            let ((l_rows, l_cols), (r_rows, r_cols)) = (left.dims(), right.dims());
+
+            return Self::error(format!("Matrices must have the same dimensions, but got '{l_rows}x{l_cols}' and '{r_rows}x{r_cols}'"))
         }
+
+        Ok(Box::new(left.try_zip_map(right, Self::execute_sum)?))
     }
 
-    fn execute_power(left: &Box<dyn Val>, right: &Box<dyn Val>) -> Box<dyn Val> {            
-        if let Some(set) = left.downcast_ref::<Rc<CanonSet>>() {
-            if InfiniteSet::Nat.contains(right) {
-                todo!()
-            } else {
-                panic!("'{right}' is not in 'Nat'");
+    fn execute_prod(left: &dyn Val, right: &dyn Val) -> error::Result<Box<dyn Val>> {
+        // A string times a natural number repeats it, from either side
+        if let Some(string) = left.downcast_ref::<String>() {
+            Self::execute_repeat(string, right)
+        } else if let Some(string) = right.downcast_ref::<String>() {
+            Self::execute_repeat(string, left)
+        // Matrix * Matrix
+        } else if let (Some(l_mat), Some(r_mat)) = (left.downcast_ref::<Matrix>(), right.downcast_ref::<Matrix>()) {
+            Self::execute_mat_prod(l_mat, r_mat)
+        // A scalar times a Matrix or Tuple multiplies each element, from either side
+        } else if (left.is_mat() || left.is_tup()) && NumVal::from_val(right).is_some() {
+            Self::execute_scaled(left, |element| Self::execute_prod(element, right))
+        } else if (right.is_mat() || right.is_tup()) && NumVal::from_val(left).is_some() {
+            Self::execute_scaled(right, |element| Self::execute_prod(left, element))
+        // Bools are multiplied as 0 and 1, even with each other
+        } else if let Some((l_num, r_num, _)) = coerce_pair(left, right) {
+            match (l_num, r_num) {
+                (NumVal::Int(l), NumVal::Int(r)) => Ok(Box::new(l * r)),
+                (NumVal::Real(l), NumVal::Real(r)) => Ok(Box::new(l * r)),
+                (NumVal::Complex(l), NumVal::Complex(r)) => Ok(Box::new(l * r)),
+                _ => unreachable!()
             }
         } else {
-            if left.is_str() || right.is_str() {
-                panic!("Cannot apply binary operator '^' to text")
-            // BigInt ^ _
-            } else if let Ok(l_bigint) = left.downcast::<BigInt>() {
-                // Exponentiating BigInt
-                if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                    if *r_bigint == BigInt::zero() {
-                        if *l_bigint == BigInt::zero() {
-                            panic!("Cannot raise '0' to the power of '0'")
-                        } else {
-                            return Box::new(BigInt::one())
-                        }
-                    }
+            Self::operator_error("*", &[left, right])
+        }
+    }
 
-                    let v = r_bigint.to_u32_digits();
+    /// Repeats `string` `count` times, where `count` must be a natural number.
+    fn execute_repeat(string: &str, count: &dyn Val) -> error::Result<Box<dyn Val>> {
+        let Some(times) = count.as_bigint().and_then(|int| int.to_usize()) else {
+            return Self::error(format!("Cannot multiply a string by '{count}', because it is not a natural number"))
+        };
 
-                    // Don't have to check if v.1.len() > 1 for r_x = 0 or 1, because the len for them won't be > 1
+        Ok(Box::new(string.repeat(times)))
+    }
 
-                    let res: Box<dyn Val>;
-                    if v.0 != Sign::Minus {
-                        res = Box::new(l_bigint.pow(if v.1.len() > 1 {
-                            panic!("Exponent is too large to compute");
-                        } else {
-                            v.1[0]
-                        }))
-                    } else {
-                        if *l_bigint == BigInt::zero() {
-                            panic!("Base of negative exponent cannot be '0'")
-                        } else if *l_bigint == BigInt::one() {
-                            res = Box::new(BigInt::one());
-                        } else if v.1.len() > 1 {
+    /// Multiplies two matrices, where the left's column count must equal the right's row count.
+    fn execute_mat_prod(left: &Matrix, right: &Matrix) -> error::Result<Box<dyn Val>> {
+        let ((l_rows, l_cols), (r_rows, r_cols)) = (left.dims(), right.dims());
+
+        if l_cols != r_rows {
+            return Self::error(format!("Cannot multiply a '{l_rows}x{l_cols}' matrix by a '{r_rows}x{r_cols}' matrix, as {l_cols} columns don't match {r_rows} rows"))
+        }
+
+        let mut rows = Vec::with_capacity(l_rows);
+
+        for row in 0..l_rows {
+            let mut elements = Vec::with_capacity(r_cols);
+
+            for col in 0..r_cols {
+                let mut sum: Box<dyn Val> = Box::new(BigInt::zero());
+
+                for k in 0..l_cols {
+                    sum = Self::execute_sum(sum.as_ref(), Self::execute_prod(left.get(row, k), right.get(k, col))?.as_ref())?;
+                }
+
+                elements.push(sum);
+            }
+
+            rows.push(elements);
+        }
+
+        Ok(Box::new(Matrix::from_rows(rows)))
+    }
+
+    /// Applies `f` to each element of a Matrix or Tuple, keeping its shape.
+    fn execute_scaled<F: FnMut(&dyn Val) -> error::Result<Box<dyn Val>>>(value: &dyn Val, mut f: F) -> error::Result<Box<dyn Val>> {
+        if let Some(mat) = value.downcast_ref::<Matrix>() {
+            Ok(Box::new(mat.try_map(f)?))
+        } else if let Some(Tuple(elements)) = value.downcast_ref() {
+            Ok(Box::new(Tuple(elements.iter().map(|element| f(element.as_ref())).collect::<error::Result<_>>()?)))
+        } else {
+            unreachable!()
+        }
+    }
+
+    fn execute_quot(left: &dyn Val, right: &dyn Val) -> error::Result<Box<dyn Val>> {
+        if left.is_str() || right.is_str() {
+            Self::operator_error("/", &[left, right])
+        // Matrix / scalar and Tuple / scalar divide each element by the scalar
+        } else if left.is_mat() || left.is_tup() {
+            match NumVal::from_val(right) {
+                Some(r_num) if r_num.is_zero() => return Self::error("Cannot divide by '0'"),
+                Some(_) => (),
+                None => return Self::error(format!("'{left}' can only be divided by a number, not '{right}'"))
+            }
+
+            if let Some(l_mat) = left.downcast_ref::<Matrix>() {
+                Ok(Box::new(l_mat.try_map(|element| Self::execute_quot(element, right))?))
+            } else if let Some(Tuple(elements)) = left.downcast_ref() {
+                Ok(Box::new(Tuple(elements.iter().map(|element| Self::execute_quot(element.as_ref(), right)).collect::<error::Result<_>>()?)))
+            } else {
+                unreachable!()
+            }
+        } else if right.is_mat() || right.is_tup() {
+            Self::error(format!("Cannot divide by '{right}', because it is not a number"))
+        } else if let Some((l_num, r_num, _)) = coerce_pair(left, right) {
+            if r_num.is_zero() {
+                return Self::error("Cannot divide by '0'")
+            }
+
+            match (l_num, r_num) {
+                // Int / Int is exact, so it becomes a BigRational
+                (NumVal::Int(l), NumVal::Int(r)) => Ok(Box::new(BigRational::new(l, r))),
+                (NumVal::Real(l), NumVal::Real(r)) => Ok(Box::new(l / r)),
+                (NumVal::Complex(l), NumVal::Complex(r)) => Ok(Box::new(l / r)),
+                _ => unreachable!()
+            }
+        } else {
+            Self::operator_error("/", &[left, right])
+        }
+    }
+
+    fn execute_power(&mut self, left: &dyn Val, right: &dyn Val) -> error::Result<Box<dyn Val>> {
+        if let Some(set) = left.downcast_ref::<Rc<CanonSet>>() {
+            if InfiniteSet::Nat.contains(right) {
+                let Some(n) = right.as_bigint().and_then(|n| n.to_usize()) else {
+                    return Self::error("Exponent is too large to compute");
+                };
+
+                // Only a finite base can be expanded into its tuples, and `A ^ 0` is always just the empty tuple
+                let power = match set.as_ref() {
+                    CanonSet::Finite(finite) => {
+                        self.check_set_size(finite.len().saturating_pow(n.try_into().unwrap_or(u32::MAX)))?;
+
+                        CanonSet::Finite(finite.cartesian_power(n))
+                    }
+                    _ if n == 0 => CanonSet::Finite(FiniteSet::new(HashSet::from([Box::new(Tuple(vec![])) as Box<dyn Val>]))),
+                    _ => CanonSet::Power(Rc::clone(set), n)
+                };
+
+                Ok(Box::new(self.set_pool.intern(&Rc::new(power))))
+            } else {
+                Self::error(format!("'{right}' is not in 'Nat'"))
+            }
+        } else if left.is_str() || right.is_str() {
+            Self::operator_error("^", &[left, right])
+        } else {
+            // The base and exponent aren't promoted to a common kind, as a rational base with an integer exponent is still an integer power
+            let (Some(base), Some(exp)) = (NumVal::from_val(left), NumVal::from_val(right)) else {
+                return Self::operator_error("^", &[left, right])
+            };
+
+            // An exponent that is an integer by value, like `4/2` or `2+0i`, is still an integer power
+            let exp = match (exp, right.as_bigint()) {
+                (NumVal::Real(_) | NumVal::Complex(_), Some(int)) => NumVal::Int(int),
+                (exp, _) => exp
+            };
+
+            match (base, exp) {
+                // BigInt ^ BigInt
+                (NumVal::Int(l_bigint), NumVal::Int(r_bigint)) => {
+                    if r_bigint == BigInt::zero() {
+                        if l_bigint == BigInt::zero() {
+                            return Self::error("Cannot raise '0' to the power of '0'")
+                        } else {
+                            return Ok(Box::new(BigInt::one()))
+                        }
+                    }
+
+                    let v = r_bigint.to_u32_digits();
+
+                    // Don't have to check if v.1.len() > 1 for r_x = 0 or 1, because the len for them won't be > 1
+
+                    let res: Box<dyn Val>;
+                    if v.0 != Sign::Minus {
+                        res = Box::new(l_bigint.pow(if v.1.len() > 1 {
+                            return Self::error("Exponent is too large to compute");
+                        } else {
+                            v.1[0]
+                        }))
+                    } else {
+                        if l_bigint == BigInt::zero() {
+                            return Self::error("Base of negative exponent cannot be '0'")
+                        } else if l_bigint == BigInt::one() {
+                            res = Box::new(BigInt::one());
+                        } else if v.1.len() > 1 {
                             // approximate with pow=-inf, aka result=0
-                            res = Box::new(BigInt::zero())
+                            res = self.approximate(format_args!("{left} ^ {right}"), Box::new(BigInt::zero()))?
                         } else {
                             res = Box::new(BigRational::new(BigInt::one(), l_bigint.pow(v.1[0])))
                         }
                     };
 
-                    res
-                // Exponentiating BigRational
-                } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                    todo!()
-                // Exponentiating Complex
-                } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                    todo!()
-                // Exponentiating Bools
-                } else if let Ok(bool) = right.downcast::<bool>() {
-                    Box::new(l_bigint.pow(*bool as u32))
-                } else {
-                    panic!("Cannot apply binary operator '^'")
+                    Ok(res)
                 }
-            // BigRational ^ _
-            } else if let Ok(l_bigrat) = left.downcast::<BigRational>() {
-                // Exponentiating BigInt
-                if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                    if *r_bigint == BigInt::zero() {
-                        if *l_bigrat == BigRational::zero() {
-                            panic!("Cannot raise '0' to the power of '0'")
+                // BigRational ^ BigInt
+                (NumVal::Real(l_bigrat), NumVal::Int(r_bigint)) => {
+                    if r_bigint == BigInt::zero() {
+                        if l_bigrat == BigRational::zero() {
+                            return Self::error("Cannot raise '0' to the power of '0'")
                         } else {
-                            return Box::new(BigInt::one())
+                            return Ok(Box::new(BigInt::one()))
                         }
                     }
                     
@@ -712,39 +1406,39 @@ impl Interpreter {
                     let res: Box<dyn Val>;
 
                     // left > 1
-                    if *l_bigrat >= BigRational::one() {
+                    if l_bigrat >= BigRational::one() {
                         if v.0 != Sign::Minus {
                             if v.1.len() > 1 {
-                                panic!("Exponent is too large to compute")
+                                return Self::error("Exponent is too large to compute")
                             } else {
                                 res = Box::new(l_bigrat.pow(v.1[0]))
                             }
                         } else {
                             if v.1.len() > 1 {
                                 // approximate with result=0
-                                res = Box::new(BigInt::zero())
+                                res = self.approximate(format_args!("{left} ^ {right}"), Box::new(BigInt::zero()))?
                             } else {
                                 res = Box::new(l_bigrat.pow(v.1[0]).recip())
                             }
                         }
                     // 0 < left < 1
-                    } else if *l_bigrat > BigRational::zero() {
+                    } else if l_bigrat > BigRational::zero() {
                         if v.0 != Sign::Minus {
                             if v.1.len() > 1 {
                                 // approximate with result=0
-                                res = Box::new(BigInt::zero())
+                                res = self.approximate(format_args!("{left} ^ {right}"), Box::new(BigInt::zero()))?
                             } else {
                                 res = Box::new(l_bigrat.pow(v.1[0]))
                             }
                         } else {
                             if v.1.len() > 1 {
-                                panic!("Exponent is too large to compute")
+                                return Self::error("Exponent is too large to compute")
                             } else {
                                 res = Box::new(l_bigrat.pow(v.1[0]).recip())
                             }
                         }
                     // left == 0
-                    } else if *l_bigrat == BigRational::zero() {
+                    } else if l_bigrat == BigRational::zero() {
                         if v.0 != Sign::Minus {
                             if v.1.len() > 1 {
                                 res = Box::new(BigInt::zero())
@@ -752,27 +1446,27 @@ impl Interpreter {
                                 res = Box::new(l_bigrat.pow(v.1[0]))
                             }
                         } else {
-                            panic!("Base of negative exponent cannot be '0'")
+                            return Self::error("Base of negative exponent cannot be '0'")
                         }
                     // -1 < left < 0
-                    } else if *l_bigrat > BigRational::one().neg() {
+                    } else if l_bigrat > BigRational::one().neg() {
                         if v.0 != Sign::Minus {
                             if v.1.len() > 1 {
                                 // approx with result=0
-                                res = Box::new(BigInt::zero())
+                                res = self.approximate(format_args!("{left} ^ {right}"), Box::new(BigInt::zero()))?
                             } else {
                                 res = Box::new(l_bigrat.pow(v.1[0]))
                             }
                         } else {
                             if v.1.len() > 1 {
-                                panic!("Exponent too large to compute")
+                                return Self::error("Exponent too large to compute")
                             } else {
                                 res = Box::new(l_bigrat.pow(v.1[0]).recip())
                             }
                         }
                     // left == -1 : flips between 1 and -1
-                    } else if *l_bigrat == BigRational::one().neg() {
-                        if *r_bigint % 2 == BigInt::zero() {
+                    } else if l_bigrat == BigRational::one().neg() {
+                        if r_bigint % 2 == BigInt::zero() {
                             res = Box::new(BigInt::one())
                         } else {
                             res = Box::new(BigInt::one().neg())
@@ -781,81 +1475,371 @@ impl Interpreter {
                     } else {
                         if v.0 != Sign::Minus {
                             if v.1.len() > 1 {
-                                panic!("Exponent too large to compute")
+                                return Self::error("Exponent too large to compute")
                             } else {
                                 res = Box::new(l_bigrat.pow(v.1[0]))
                             }
                         } else {
                             if v.1.len() > 1 {
                                 // approx with result=0
-                                res = Box::new(BigInt::zero())
+                                res = self.approximate(format_args!("{left} ^ {right}"), Box::new(BigInt::zero()))?
                             } else {
                                 res = Box::new(l_bigrat.pow(v.1[0]).recip())
                             }
                         }
                     }
 
-                    res
-                // Exponentiating BigRational
-                } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                    todo!()
-                // Exponentiating Complex
-                } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                    todo!()
-                // Exponentiating Bools
-                } else if let Ok(bool) = right.downcast::<bool>() {
-                    Box::new(l_bigrat.pow(*bool as u32))
-                } else {
-                    panic!("Cannot apply binary operator '^'")
+                    Ok(res)
                 }
-            // Complex ^ _
-            } else if let Ok(l_complex) = left.downcast::<Complex<BigRational>>() {
-                // Dividing BigInt
-                if let Ok(r_bigint) = right.downcast::<BigInt>() {
-                    todo!()
-                // Dividing BigRational
-                } else if let Ok(r_bigrat) = right.downcast::<BigRational>() {
-                    todo!()
-                // Exponentiating Complex
-                } else if let Ok(r_complex) = right.downcast::<Complex<BigRational>>() {
-                    todo!()
-                // Exponentiating Bools
-                } else if let Ok(_) = right.downcast::<bool>() {
-                    todo!()
-                } else {
-                    panic!("Cannot apply binary operator '^'")
+                // Complex ^ BigInt
+                (NumVal::Complex(l_complex), NumVal::Int(r_bigint)) => {
+                    if r_bigint == BigInt::zero() {
+                        if l_complex == Complex::zero() {
+                            return Self::error("Cannot raise '0' to the power of '0'")
+                        } else {
+                            return Ok(Box::new(BigInt::one()))
+                        }
+                    }
+
+                    let v = r_bigint.to_u32_digits();
+
+                    if v.1.len() > 1 {
+                        return Self::error("Exponent is too large to compute")
+                    }
+
+                    if v.0 != Sign::Minus {
+                        Ok(Box::new(l_complex.powu(v.1[0])))
+                    } else if l_complex == Complex::zero() {
+                        Self::error("Base of negative exponent cannot be '0'")
+                    } else {
+                        Ok(Box::new(l_complex.powu(v.1[0]).inv()))
+                    }
                 }
-            // Cannot use division with booleans
-            } else if let Ok(_) = left.downcast::<bool>() {
-                panic!("Cannot use division with booleans")
-            } else {
-                panic!("Cannot apply binary operator '/'")
+                // BigInt ^ BigRational, BigRational ^ BigRational: only exact when the base is a perfect power, as in 27^(2/3) = 3^2
+                (base @ (NumVal::Int(_) | NumVal::Real(_)), NumVal::Real(r_bigrat)) => {
+                    let Some(n) = r_bigrat.denom().to_u32() else {
+                        return Self::error("Exponent is too large to compute")
+                    };
+
+                    let root: Option<Box<dyn Val>> = match base {
+                        NumVal::Int(l_bigint) => Self::exact_root(&l_bigint, n).map(|root| Box::new(root) as Box<dyn Val>),
+                        NumVal::Real(l_bigrat) => match (Self::exact_root(l_bigrat.numer(), n), Self::exact_root(l_bigrat.denom(), n)) {
+                            (Some(numer), Some(denom)) => Some(Box::new(BigRational::new(numer, denom))),
+                            _ => None
+                        },
+                        _ => unreachable!()
+                    };
+
+                    let Some(root) = root else {
+                        return Self::error(format!("Cannot raise '{left}' to the power of '{right}', because there is no exact rational result"))
+                    };
+
+                    self.execute_power(root.as_ref(), &r_bigrat.numer().to_owned())
+                }
+                // Complex ^ BigRational
+                (NumVal::Complex(_), NumVal::Real(_)) => Self::error(format!("Cannot raise '{left}' to the power of '{right}', because roots of complex numbers aren't supported yet")),
+                // _ ^ Complex
+                (_, NumVal::Complex(_)) => Self::error(format!("Cannot raise '{left}' to the power of '{right}', because complex exponents aren't supported yet"))
             }
         }
     }
 
-    fn execute_set(&mut self, exprs: &[Box<dyn Expr>]) -> Box<dyn Val> {
+    /// Returns the `n`th root of `x`, if it is an integer.
+    fn exact_root(x: &BigInt, n: u32) -> Option<BigInt> {
+        if x.sign() == Sign::Minus && n.is_multiple_of(2) {
+            return None
+        }
+
+        let root = x.nth_root(n);
+
+        if &Pow::pow(&root, n) == x {
+            Some(root)
+        } else {
+            None
+        }
+    }
+
+    fn execute_set(&mut self, exprs: &[Box<dyn Expr>]) -> error::Result<Box<dyn Val>> {
+        self.check_set_size(exprs.len())?;
+
         let mut set = HashSet::<Box<dyn Val>>::new();
 
         for expr in exprs {
-            set.insert(self.execute_expr(expr));
+            let value = self.execute_expr(expr.as_ref())?;
+
+            // A set hashes its elements, which a function can only do if its body can be compared
+            if let Some(func) = value.downcast_ref::<Func>() {
+                func.check_comparable()?;
+            }
+
+            set.insert(value);
         }
 
-        Box::new(Rc::new(CanonSet::Finite(FiniteSet::new(set))))
+        Ok(Box::new(Rc::new(CanonSet::Finite(FiniteSet::new(set)))))
     }
 
-    fn execute_assign(&mut self, name: &str, right: &Box<dyn Expr>) -> Box<dyn Val> {
+    /// Enumerates the source of a set builder, which has to be a finite set, as a set is built all at once.
+    fn enumerate_source(source: &dyn Val) -> error::Result<Box<dyn ValIterator>> {
+        let Some(set) = source.downcast_ref::<Rc<CanonSet>>() else {
+            return Self::error(format!("Cannot build a set from '{source}', because it is not a set"))
+        };
+
+        match set.enumerate() {
+            Some(_) if !set.is_finite() => Self::error(format!("Cannot build a set from '{source}', because it is infinite")),
+            Some(elements) => Ok(elements),
+            None => Self::error(format!("Cannot build a set from '{source}', because it cannot be enumerated"))
+        }
+    }
+
+    /// Builds `{ name : name <: source & cond }` by enumerating `source`, and keeping each element for which `cond` is true.
+    fn execute_set_builder(&mut self, name: &str, source: &dyn Expr, cond: Option<&dyn Expr>) -> error::Result<Box<dyn Val>> {
+        let source = self.execute_expr(source)?;
+        let elements = Self::enumerate_source(source.as_ref())?;
+
+        let mut set = HashSet::<Box<dyn Val>>::new();
+
+        for element in elements {
+            if let Some(cond) = cond {
+                let mut env = Env::new(Some(Rc::clone(&self.env)));
+                env.insert_sym(name.to_owned(), element.clone());
+
+                let kept = self.scoped(env).execute_expr(cond)?;
+
+                match kept.downcast_ref::<bool>() {
+                    Some(true) => (),
+                    Some(false) => continue,
+                    None => return Self::error(format!("The condition of a set builder must be a bool, but got '{kept}'"))
+                }
+            }
+
+            set.insert(element);
+        }
+
+        Ok(Box::new(Rc::new(CanonSet::Finite(FiniteSet::new(set)))))
+    }
+
+    /// Builds `{ output | x : A, y : B & cond }` by enumerating every combination of elements of the sources, and keeping `output` for each one where `cond` is true.
+    fn execute_comprehension(&mut self, output: &dyn Expr, binders: &[(Symbol, Box<dyn Expr>)], cond: Option<&dyn Expr>) -> error::Result<Box<dyn Val>> {
+        let sources = binders
+            .iter()
+            .map(|(_, source)| {
+                let source = self.execute_expr(source.as_ref())?;
+
+                Ok(Self::enumerate_source(source.as_ref())?.collect::<Vec<_>>())
+            })
+            .collect::<error::Result<Vec<_>>>()?;
+
+        let combinations = sources.iter().fold(1usize, |count, elements| count.saturating_mul(elements.len()));
+
+        self.check_set_size(combinations)?;
+
+        let mut set = HashSet::<Box<dyn Val>>::new();
+
+        // Count through the combinations like an odometer, with the last variable changing fastest
+        let mut indices = vec![0; sources.len()];
+
+        for _ in 0..combinations {
+            let mut env = Env::new(Some(Rc::clone(&self.env)));
+
+            for ((Symbol(name), _), (elements, &i)) in binders.iter().zip(sources.iter().zip(&indices)) {
+                env.insert_sym(name.to_owned(), elements[i].clone());
+            }
+
+            let mut scoped = self.scoped(env);
+
+            let kept = match cond {
+                Some(cond) => {
+                    let kept = scoped.execute_expr(cond)?;
+
+                    match kept.downcast_ref::<bool>() {
+                        Some(&kept) => kept,
+                        None => return Self::error(format!("The condition of a set builder must be a bool, but got '{kept}'"))
+                    }
+                }
+                None => true
+            };
+
+            if kept {
+                set.insert(scoped.execute_expr(output)?);
+            }
+
+            for (i, elements) in indices.iter_mut().zip(&sources).rev() {
+                *i += 1;
+
+                if *i < elements.len() {
+                    break
+                }
+
+                *i = 0;
+            }
+        }
+
+        Ok(Box::new(Rc::new(CanonSet::Finite(FiniteSet::new(set)))))
+    }
+
+    /// Executes `f . g`, which is the function computing `f(g(...))`, taking `g`'s arguments and giving values in `f`'s codomain.
+    /// 
+    /// If `f` takes more than one argument, `g` has to give a tuple of that many values, like `g(x) = (x, 2x)`, each of which is passed on separately.
+    fn execute_compose(left: &dyn Val, right: &dyn Val) -> error::Result<Box<dyn Val>> {
+        let (Some(f), Some(g)) = (left.downcast_ref::<Func>(), right.downcast_ref::<Func>()) else {
+            return Self::error(format!("Cannot compose '{left}' and '{right}', because only functions can be composed"))
+        };
+
+        let outputs: Vec<Option<Box<dyn Expr>>> = match g.expr().downcast_ref::<expr::Tuple>() {
+            Some(expr::Tuple(exprs)) if f.arity() != 1 => exprs
+                .iter()
+                .map(|expr| Some(expr.to_owned()))
+                .collect(),
+            _ => vec![Some(g.expr().clone_expr())]
+        };
+
+        if outputs.len() != f.arity() {
+            return Self::error(format!("Cannot compose '{left}' and '{right}', because the inner function gives {} value(s), but the outer function takes {}", outputs.len(), f.arity()))
+        }
+
+        Ok(Box::new(Func::new(
+            Rc::clone(g.env()),
+            g.args(),
+            Box::new(Call(Box::new(Group(Box::new(Literal(left.clone_box())))), outputs)),
+            f.codomain(),
+            g.settings()
+        )))
+    }
+
+    /// Gives `approx` for a result that is too small to compute exactly, unless the interpreter is keeping results exact, which makes it an error.
+    fn approximate(&self, result: fmt::Arguments, approx: Box<dyn Val>) -> error::Result<Box<dyn Val>> {
+        if self.settings.exact {
+            return Self::error(format!("Cannot compute '{result}' exactly"))
+        }
+
+        Ok(approx)
+    }
+
+    /// Executes `&&` or `||`, short-circuiting: `right` is only executed if `left` doesn't already decide the result.
+    fn execute_logical(&mut self, left: &dyn Expr, op: &Token, right: &dyn Expr) -> error::Result<Box<dyn Val>> {
+        let expect_bool = |value: Box<dyn Val>| if let Some(&bool) = value.downcast_ref::<bool>() {
+            Ok(bool)
+        } else {
+            Self::error(format!("Operator '{}' expects booleans, but got '{value}'", op.lexeme()))
+        };
+
+        let left = expect_bool(self.execute_expr(left)?)?;
+
+        Ok(match (op.kind(), left) {
+            (TokenKind::DblAmp, false) => Box::new(false),
+            (TokenKind::DblBar, true) => Box::new(true),
+            _ => Box::new(expect_bool(self.execute_expr(right)?)?)
+        })
+    }
+
+    /// Executes a comparison operator. `==` and `!=` work on any values, while `<`, `>`, `<=`, and `>=` compare two real numbers, or two strings (lexicographically).
+    fn execute_comparison(&self, left: &dyn Val, op: &Token, right: &dyn Val) -> error::Result<bool> {
+        let equals = |left: &dyn Val, right: &dyn Val| if self.settings.strict_equality {
+            left.compare_strict(right)
+        } else {
+            left.compare(right)
+        };
+
+        match op.kind() {
+            TokenKind::DblEq => return Ok(equals(left, right)),
+            TokenKind::BangEq => return Ok(!equals(left, right)),
+            _ => ()
+        }
+
+        // Sorting puts values of different types in order, but comparing them is still a mistake
+        let ordering = if left.type_rank() == right.type_rank() {
+            left.cmp_val(right)
+        } else {
+            None
+        };
+
+        let ordering = match ordering {
+            Some(ordering) => ordering,
+            // Complex numbers have no order, unless they are real by value
+            None if left.is_num() && right.is_num() => {
+                return Self::error(format!("Cannot apply comparison operator '{}' to complex numbers '{left}' and '{right}'", op.lexeme()))
+            }
+            None => return Self::operator_error(op.lexeme(), &[left, right])
+        };
+
+        Ok(match op.kind() {
+            TokenKind::Less => ordering.is_lt(),
+            TokenKind::Greater => ordering.is_gt(),
+            TokenKind::LessEq => ordering.is_le(),
+            TokenKind::GreaterEq => ordering.is_ge(),
+            _ => unreachable!()
+        })
+    }
+
+    /// Executes a binary set operator (`|`, `&`, `\`, or `~`), and interns the canonicalized result.
+    fn execute_set_op(&mut self, left: &dyn Val, op: &Token, right: &dyn Val) -> error::Result<Box<dyn Val>> {
+        let (l_set, r_set) = match (left.downcast_ref::<Rc<CanonSet>>(), right.downcast_ref::<Rc<CanonSet>>()) {
+            (Some(l_set), Some(r_set)) => (Rc::clone(l_set), Rc::clone(r_set)),
+            (None, _) => return Self::error(format!("Cannot apply set operator '{}' to '{left}', because it is not a set", op.lexeme())),
+            (_, None) => return Self::error(format!("Cannot apply set operator '{}' to '{right}', because it is not a set", op.lexeme()))
+        };
+
+        // Only a union can be larger than its operands
+        if let (TokenKind::Bar, CanonSet::Finite(a), CanonSet::Finite(b)) = (op.kind(), l_set.as_ref(), r_set.as_ref()) {
+            self.check_set_size(a.len() + b.len())?;
+        }
+
+        let set = match op.kind() {
+            TokenKind::Bar => CanonSet::Union(l_set, r_set),
+            TokenKind::Amp => CanonSet::Intersect(l_set, r_set),
+            TokenKind::BackSlash => CanonSet::Exclusion(l_set, r_set),
+            TokenKind::Tilde => CanonSet::SymDiff(l_set, r_set),
+            _ => unreachable!()
+        };
+
+        Ok(Box::new(self.set_pool.intern(&canon(Rc::new(set)))))
+    }
+
+    /// Executes a set relation: `=:` is equality, `<:` and `>:` are subset and superset, and `<=:` and `>=:` are their strict versions.
+    /// 
+    /// Sets are equal if each is a subset of the other, even if they are written differently, like `Nat | Int` and `Int`.
+    fn execute_set_relation(left: &dyn Val, op: &Token, right: &dyn Val) -> error::Result<bool> {
+        let (l_set, r_set) = match (left.downcast_ref::<Rc<CanonSet>>(), right.downcast_ref::<Rc<CanonSet>>()) {
+            (Some(l_set), Some(r_set)) => (l_set, r_set),
+            (None, _) => return Self::error(format!("'{left}' is not a set")),
+            (_, None) => return Self::error(format!("'{right}' is not a set"))
+        };
+
+        Ok(match op.kind() {
+            TokenKind::EqColon => match (l_set.as_ref(), r_set.as_ref()) {
+                // Finite sets are equal exactly when their elements are, which their saved hashes can reject quickly
+                (CanonSet::Finite(l_finite), CanonSet::Finite(r_finite)) => l_finite == r_finite,
+                _ => l_set == r_set || (l_set.is_subset(r_set) && r_set.is_subset(l_set))
+            },
+            TokenKind::LessColon => l_set.is_subset(r_set),
+            TokenKind::GreaterColon => r_set.is_subset(l_set),
+            TokenKind::LessEqColon => l_set.is_subset(r_set) && !r_set.is_subset(l_set),
+            TokenKind::GreaterEqColon => r_set.is_subset(l_set) && !l_set.is_subset(r_set),
+            _ => unreachable!()
+        })
+    }
+
+    /// Checks that a finite set of (at most) `size` elements may be built, before building it.
+    fn check_set_size(&self, size: usize) -> Result<(), RuntimeError> {
+        if size > self.settings.max_set_size {
+            Err(RuntimeError::SetTooLarge { size, max: self.settings.max_set_size })
+        } else {
+            Ok(())
+        }
+    }
+
+    fn execute_assign(&mut self, name: &str, right: &dyn Expr) -> error::Result<Box<dyn Val>> {
         if RefCell::borrow(&self.env).is_sym_assigned(name) {
-            panic!("Variable {name} cannot be reassigned")
+            return Self::error(format!("Variable {name} cannot be reassigned"))
         }
 
-        let mut right = self.execute_expr(right);
+        let mut right = self.execute_expr(right)?;
 
         if let Ok(func) = right.downcast::<Func>() {
             // function name already has a map type
             if let Some(SymStore::FuncType(arg_types, codomain)) = self.env.borrow_mut().get(name) {
                 if func.arity() != arg_types.len() {
-                    panic!("Function '{name}' was previously denoted to have {} arguments, but is declared to have {} instead.", arg_types.len(), func.arity())
+                    return Self::error(format!("Function '{name}' was previously denoted to have {} arguments, but is declared to have {} instead.", arg_types.len(), func.arity()))
                 }
 
                 let mut new_env = Env::from_env(func.env());
@@ -866,13 +1850,19 @@ impl Interpreter {
                     new_env.insert_sym_type(arg_name.to_owned(), self.set_pool.intern(typeset));
                 }
 
-                right = Box::new(func.clone_with_env(Rc::new(RefCell::new(new_env))));
+                right = Box::new(Func::new(
+                    Rc::new(RefCell::new(new_env)),
+                    func.args(),
+                    func.expr().clone_expr(),
+                    &self.set_pool.intern(&codomain),
+                    &self.settings
+                ));
             }
         } else {
             
             if let Some(SymStore::Type(typeset)) = RefCell::borrow(&self.env).get(name) {
-                if !typeset.contains(&right) {
-                    panic!("'{name}' is in '{typeset}' which does not contain '{right}'")
+                if !typeset.try_contains(right.as_ref())? {
+                    return Self::error(format!("'{name}' is in '{typeset}' which does not contain '{right}'"))
                 }
             }
         }
@@ -882,26 +1872,800 @@ impl Interpreter {
             right.clone()
         );
 
-        right
+        Ok(right)
     }
 
-    fn execute_typed_assign(&mut self, name: &str, typeset: &Box<dyn Expr>, right: &Box<dyn Expr>) {
+    /// Describes why `value` can't be given the type `set`. A finite set lists what the value could have been, so it is a missing element rather than the wrong type.
+    fn not_in_type(value: &dyn Val, set: &Rc<CanonSet>) -> String {
+        if let CanonSet::Finite(_) = set.as_ref() {
+            format!("'{value}' is not an element of '{set}'")
+        } else {
+            format!("'{value}' is not in '{set}'")
+        }
+    }
+
+    /// Casts `value` into `typeset`, which must contain it. Numbers are converted to the representation the set names, so `3 : Real` is a rational and `4/2 : Int` is an integer.
+    fn execute_cast(&mut self, value: &dyn Expr, typeset: &dyn Expr) -> error::Result<Box<dyn Val>> {
+        let value = self.execute_expr(value)?;
+        let typeset = self.execute_expr(typeset)?;
+
+        let Some(set) = typeset.downcast_ref::<Rc<CanonSet>>() else {
+            return Self::error(format!("'{typeset}' is not a set"))
+        };
+
+        if !set.try_contains(value.as_ref())? {
+            return Self::error(Self::not_in_type(value.as_ref(), set))
+        }
+
+        Ok(match (set.as_ref(), value.as_number()) {
+            (CanonSet::Infinite(InfiniteSet::Nat | InfiniteSet::Int), Some(_)) => Box::new(value.as_bigint().unwrap()),
+            (CanonSet::Infinite(InfiniteSet::Real), Some(_)) => Box::new(value.as_bigrational().unwrap()),
+            (CanonSet::Infinite(InfiniteSet::Complex), Some(num)) => match num.promote(NumKind::Complex) {
+                NumVal::Complex(complex) => Box::new(complex),
+                _ => unreachable!()
+            },
+            _ => value
+        })
+    }
+
+    fn execute_typed_assign(&mut self, name: &str, typeset: &dyn Expr, right: &dyn Expr) -> error::Result<()> {
         if RefCell::borrow(&self.env).is_sym_assigned(name) {
-            panic!("Variable '{name}' cannot be reassigned")
+            return Self::error(format!("Variable '{name}' cannot be reassigned"))
         }
 
-        let typeset = self.execute_expr(typeset);
+        let typeset = self.execute_expr(typeset)?;
 
         if let Some(set) = typeset.downcast_ref::<Rc<CanonSet>>() {
-            let value = self.execute_expr(right);
+            let value = self.execute_expr(right)?;
 
-            if set.contains(&value) {
+            if set.try_contains(value.as_ref())? {
                 self.env.borrow_mut().insert_sym(name.to_owned(), value);
+                Ok(())
             } else {
-                panic!("Incompatible types: '{value}' cannot be cast into '{typeset}'");
+                Self::error(Self::not_in_type(value.as_ref(), set))
             }
         } else {
-            panic!("'{typeset}' is not a set");
+            Self::error(format!("'{typeset}' is not a set"))
+        }
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Interpreter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interpreter")
+            .field("env", &self.env)
+            .field("set_pool", &self.set_pool)
+            .field("settings", &self.settings)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    /// Collects what the interpreter writes, so that it can be read back after running.
+    #[derive(Clone, Default)]
+    struct Output(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Runs `source` with `interpreter`, and returns what it wrote, one line per echoed value.
+    fn run_with(interpreter: &mut Interpreter, source: &str) -> error::Result<String> {
+        let output = Output::default();
+        interpreter.set_output(Box::new(output.clone()));
+
+        let tokens = Lexer::new(source.as_bytes()).lex()?;
+        interpreter.interpret(Parser::new(&tokens).parse()?.stmts())?;
+
+        let written = RefCell::borrow(&output.0).clone();
+        Ok(String::from_utf8(written).unwrap())
+    }
+
+    fn run(source: &str) -> error::Result<String> {
+        run_with(&mut Interpreter::new(), source)
+    }
+
+    thread_local! {
+        static TICKS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    /// Returns its argument, counting how many times it was called, so tests can see what was evaluated.
+    fn tick(args: &[Box<dyn Val>]) -> error::Result<Box<dyn Val>> {
+        TICKS.with(|ticks| ticks.set(ticks.get() + 1));
+        Ok(args[0].clone())
+    }
+
+    /// Returns how many times `tick` was called since the last time this was called.
+    fn take_ticks() -> usize {
+        TICKS.with(|ticks| ticks.replace(0))
+    }
+
+    /// An interpreter which has `tick` defined.
+    fn ticking_interpreter() -> Interpreter {
+        let interpreter = Interpreter::new();
+        interpreter.env.borrow_mut().insert_sym(String::from("tick"), Box::new(NativeFunc::new("tick", 1, tick)));
+
+        take_ticks();
+        interpreter
+    }
+
+    #[test]
+    fn output_of_blocks_reaches_the_sink() {
+        assert_eq!(run("do\n  x = 1\n  do\n    x + 1\n  end\nend").unwrap(), "x = 1\n2\n");
+    }
+
+    #[test]
+    fn block_has_the_value_of_its_last_statement() {
+        assert_eq!(run("x = do 1 end").unwrap(), "x = 1\n");
+        assert_eq!(run("x = do\n  y = 2;\n  y * 3\nend").unwrap(), "6\nx = 6\n");
+        assert_eq!(run("x = do\n  y : Int\nend").unwrap(), "x = ()\n");
+        assert_eq!(run("1 + do 2 end * 3").unwrap(), "7\n");
+    }
+
+    #[test]
+    fn block_shadows_without_changing_the_outer_binding() {
+        assert_eq!(run("a = 1\nb = do\n  a = 2;\n  a + 1;\nend\na\nb").unwrap(), "a = 1\nb = 3\n1\n3\n");
+        assert!(run("c = do\n  d = 1;\nend\nd").unwrap_err().to_string().starts_with("Variable 'd' is not defined"));
+    }
+
+    #[test]
+    fn blocks_in_function_bodies() {
+        let f = "f(x) = do\n  y = x + 1;\n  y * 2;\nend\n";
+        let g = "g(z) = do\n  y = z + 1;\n  y * 2;\nend\n";
+
+        assert_eq!(run(&format!("{f}f(3)")).unwrap().lines().last(), Some("8"));
+        assert_eq!(run(&format!("{f}{g}#{{f, g}}\n(f + g)(1)")).unwrap().lines().rev().take(2).collect::<Vec<_>>(), ["8", "1"]);
+        assert_eq!(run("h(x, w) = do x + w end\nh(1)(2)").unwrap().lines().last(), Some("3"));
+    }
+
+    #[test]
+    fn failed_write_is_an_error() {
+        struct Closed;
+
+        impl Write for Closed {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
         }
+
+        let mut interpreter = Interpreter::new();
+        interpreter.set_output(Box::new(Closed));
+
+        let tokens = Lexer::new("1".as_bytes()).lex().unwrap();
+        assert!(matches!(interpreter.interpret(Parser::new(&tokens).parse().unwrap().stmts()), Err(Error::Io(_))));
+    }
+
+    #[test]
+    fn power_of_set_past_max_size_is_an_error() {
+        match run("{0, 1} ^ 30") {
+            Err(Error::Runtime(RuntimeError::SetTooLarge { size, max })) => {
+                assert_eq!(size, 1 << 30);
+                assert_eq!(max, DEFAULT_MAX_SET_SIZE);
+            }
+            result => panic!("expected a SetTooLarge error, but got {result:?}")
+        }
+    }
+
+    #[test]
+    fn union_past_max_size_is_an_error() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_set_size(3);
+
+        assert!(matches!(run_with(&mut interpreter, "{1, 2} | {3, 4}"), Err(Error::Runtime(RuntimeError::SetTooLarge { size: 4, max: 3 }))));
+    }
+
+    #[test]
+    fn max_set_size_holds_inside_functions() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_set_size(3);
+        run_with(&mut interpreter, "f(x) = x | {3, 4}").unwrap();
+
+        assert!(matches!(run_with(&mut interpreter, "f({1, 2})"), Err(Error::Runtime(RuntimeError::SetTooLarge { size: 4, max: 3 }))));
+        assert_eq!(run_with(&mut interpreter, "f({3})").unwrap(), "{3, 4}\n");
+    }
+
+    #[test]
+    fn cardinality_of_operations() {
+        assert_eq!(run("#(Nat | {-1})").unwrap(), "countably infinite\n");
+        assert_eq!(run("#(Real \\ Nat)").unwrap(), "uncountable\n");
+        assert_eq!(run("#(Real & {1, -1, \"a\"})").unwrap(), "2\n");
+    }
+
+    #[test]
+    fn cardinality_of_non_set_is_an_error() {
+        let Err(Error::Runtime(err)) = run("#1") else {
+            panic!("expected a runtime error")
+        };
+
+        assert!(err.to_string().starts_with("cannot take cardinality of '1'"));
+    }
+
+    #[test]
+    fn matrix_difference() {
+        assert_eq!(run("[2, 2; 2, 2] - [1, 1; 1, 1] == [1, 1; 1, 1]").unwrap(), "true\n");
+
+        let Err(err) = run("[1, 2; 3, 4] - [1; 2]") else {
+            panic!("expected a shape error")
+        };
+
+        assert!(err.to_string().contains("'2x2' and '2x1'"));
+    }
+
+    #[test]
+    fn tuple_difference_is_an_error() {
+        assert!(run("[1, 2] - [1, 2]").is_err());
+        assert!(run("[1, 2; 3, 4] - [1, 2]").is_err());
+    }
+
+    #[test]
+    fn functions_equal_up_to_argument_names() {
+        assert_eq!(run("f(x) = x + 1\ng(y) = y + 1\n#{f, g}").unwrap().lines().last(), Some("1"));
+    }
+
+    #[test]
+    fn functions_with_different_argument_types_differ() {
+        assert_eq!(run("f(x) = x + 1\nk(x : Nat) = x + 1\n#{f, k}").unwrap().lines().last(), Some("2"));
+    }
+
+    #[test]
+    fn functions_capturing_different_values_differ() {
+        let output = run("a = 1\nm(x) = x + a\ndo\n  a = 100\n  n(x) = x + a\n  #{m, n}\nend").unwrap();
+        assert_eq!(output.lines().last(), Some("2"));
+    }
+
+    #[test]
+    fn functions_with_casts_indices_slices_and_set_builders_compare_by_body() {
+        for (body, renamed, different) in [
+            ("x : Real", "y : Real", "y : Int"),
+            ("x[0]", "y[0]", "y[1]"),
+            ("x[0..2]", "y[0..2]", "y[1..2]"),
+            ("{n : n <: {1, 2, 3} & n < x}", "{n : n <: {1, 2, 3} & n < y}", "{n : n <: {1, 2, 3} & n <= y}")
+        ] {
+            let source = format!("f(x) = {body}\ng(y) = {renamed}\nh(y) = {different}\n#{{f, g}}\n#{{f, h}}");
+            assert_eq!(run(&source).unwrap().lines().skip(3).collect::<Vec<_>>(), ["1", "2"], "comparing '{body}'");
+        }
+    }
+
+    #[test]
+    fn function_that_cannot_be_compared_is_an_error_in_a_set() {
+        let Err(err) = run("h(x) = (y = x)\n{h}") else {
+            panic!("expected an error")
+        };
+
+        assert!(err.to_string().starts_with("'y = x' cannot be used inside a function"));
+    }
+
+    #[test]
+    fn predicate_set_membership() {
+        let source = "p(x : Nat) = x == 1\nE = setof(p)\ny : E = 1\ny";
+        assert_eq!(run(source).unwrap().lines().last(), Some("1"));
+
+        let Err(err) = run("p(x : Nat) = x == 1\nE = setof(p)\nz : E = 2") else {
+            panic!("expected 2 not to be in E")
+        };
+
+        assert!(err.to_string().starts_with("'2' is not in 'setof(x -> x == 1)'"));
+    }
+
+    #[test]
+    fn predicate_set_cardinality_follows_its_domain() {
+        assert_eq!(run("p(x : Nat) = x > 2\n#setof(p)").unwrap(), "p = x -> x > 2\nat most countable\n");
+        assert_eq!(run("p(x : {1, 2, 3}) = x > 1\n#setof(p)").unwrap(), "p = x -> x > 1\n2\n");
+        assert_eq!(run("p(x : Real) = x > 1\n#setof(p)").unwrap(), "p = x -> x > 1\nunknown\n");
+        assert_eq!(run("p(x) = x > 1\n#setof(p)").unwrap(), "p = x -> x > 1\nunknown\n");
+    }
+
+    #[test]
+    fn failing_predicate_is_a_runtime_error() {
+        let Err(Error::Runtime(err)) = run("p(x : Nat) = x == 1\nE = setof(p)\ny : E = -1") else {
+            panic!("expected a runtime error")
+        };
+
+        assert!(err.to_string().contains("doesn't contain '-1'"));
+
+        let Err(Error::Runtime(err)) = run("p(x) = 1\nE = setof(p)\ny : E = 3") else {
+            panic!("expected a runtime error")
+        };
+
+        assert!(err.to_string().contains("must return a bool"));
+    }
+
+    #[test]
+    fn predicate_sets_with_equal_functions_are_equal() {
+        let source = "p(x : Nat) = x == 1\nq(y : Nat) = y == 1\nsetof(p) =: setof(q)";
+        assert_eq!(run(source).unwrap().lines().last(), Some("true"));
+    }
+
+    #[test]
+    fn strict_equality_compares_representations() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_strict_equality(true);
+
+        assert_eq!(run_with(&mut interpreter, "2 == 2.0").unwrap(), "false\n");
+        assert_eq!(run_with(&mut interpreter, "2 != 2.0").unwrap(), "true\n");
+        assert_eq!(run("2 == 2.0").unwrap(), "true\n");
+    }
+
+    #[test]
+    fn strict_equality_holds_inside_functions() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_strict_equality(true);
+
+        let output = run_with(&mut interpreter, "f(x) = x == 2.0\nf(2)\nf(2.0)").unwrap();
+        assert_eq!(output.lines().skip(1).collect::<Vec<_>>(), ["false", "true"]);
+    }
+
+    #[test]
+    fn exact_mode_rejects_approximating_builtins() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_exact(true);
+
+        for source in ["modulus(1 + 1i)", "arg(1i)", "abs(1 + 1i)"] {
+            let Err(err) = run_with(&mut interpreter, source) else {
+                panic!("expected '{source}' to be inexact")
+            };
+
+            assert!(err.to_string().starts_with(&format!("Cannot compute '{source}' exactly")));
+        }
+
+        assert_eq!(run_with(&mut interpreter, "modulus(3 + 4i)").unwrap(), "5\n");
+        assert!(run("modulus(1 + 1i)").is_ok());
+    }
+
+    #[test]
+    fn exact_mode_rejects_approximated_powers() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_exact(true);
+
+        assert!(run_with(&mut interpreter, "(1/2) ^ (2^40)").unwrap_err().to_string().starts_with("Cannot compute"));
+        assert_eq!(run("(1/2) ^ (2^40)").unwrap(), "0\n");
+    }
+
+    #[test]
+    fn exact_mode_holds_inside_functions() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_exact(true);
+        run_with(&mut interpreter, "f(x) = (1/2)^x").unwrap();
+
+        assert!(run_with(&mut interpreter, "f(2^40)").unwrap_err().to_string().starts_with("Cannot compute"));
+        assert_eq!(run_with(&mut interpreter, "f(2)").unwrap(), "1/4\n");
+    }
+
+    #[test]
+    fn bindings_list_definitions_with_their_kinds() {
+        let mut interpreter = Interpreter::new();
+        run_with(&mut interpreter, "x = 1\ny : Nat\nf : Int, Str -> Real\ng(a) = a").unwrap();
+
+        let bindings = interpreter.bindings();
+        let kind_of = |name: &str| bindings.iter().find(|(bound, _)| bound == name).map(|(_, kind)| kind.clone());
+
+        assert_eq!(kind_of("x"), Some(SymKind::Value(String::from("1"))));
+        assert_eq!(kind_of("y"), Some(SymKind::Type(String::from("Nat"))));
+        assert_eq!(kind_of("f"), Some(SymKind::FuncType(String::from("Int, Str -> Real"))));
+        assert_eq!(kind_of("g"), Some(SymKind::Value(String::from("a -> a"))));
+        assert_eq!(kind_of("Nat"), Some(SymKind::Value(String::from("Nat"))));
+        assert!(bindings.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn predicate_sets_can_be_elements() {
+        let source = "p(x) = x == 1\nq(y) = y == 1\nS = {setof(p), setof(q), ~Nat, Nat | Real, {1}}\n#S\nsetof(p) : {setof(q)}";
+        let output = run(source).unwrap();
+        let mut lines = output.lines().skip(3);
+
+        // setof(p) and setof(q) are the same set, so there are only four elements
+        assert_eq!(lines.next(), Some("4"));
+        assert_eq!(lines.next(), Some("setof(x -> x == 1)"));
+    }
+
+    #[test]
+    fn matrix_literal_is_a_matrix() {
+        let mut interpreter = Interpreter::new();
+        assert_eq!(run_with(&mut interpreter, "m = [1, 2; 3, 4]\nm").unwrap().lines().last(), Some("[1, 2; 3, 4]"));
+
+        let Some(SymStore::Value(value)) = RefCell::borrow(&interpreter.env).get("m") else {
+            panic!("expected m to be assigned")
+        };
+        let matrix = value.downcast_ref::<Matrix>().expect("expected a matrix");
+
+        assert_eq!(matrix.dims(), (2, 2));
+        assert!(matrix.get(1, 0).compare(&BigInt::from(3)));
+    }
+
+    #[test]
+    fn set_operators() {
+        assert_eq!(run("{1, 2} | {2, 3}").unwrap(), "{1, 2, 3}\n");
+        assert_eq!(run("{1, 2} & {2, 3}").unwrap(), "{2}\n");
+        assert_eq!(run("{1, 2} \\ {2, 3}").unwrap(), "{1}\n");
+        assert_eq!(run("{1, 2} ~ {2, 3}").unwrap(), "{1, 3}\n");
+    }
+
+    #[test]
+    fn set_operator_on_non_set_is_an_error() {
+        for (source, operator) in [("2 | {1}", '|'), ("{1} & 2", '&'), ("2 \\ {1}", '\\'), ("{1} ~ 2", '~')] {
+            let Err(err) = run(source) else {
+                panic!("expected '{source}' to be an error")
+            };
+
+            assert!(err.to_string().starts_with(&format!("Cannot apply set operator '{operator}' to '2', because it is not a set")));
+        }
+    }
+
+    #[test]
+    fn chained_comparison_evaluates_each_operand_once() {
+        let mut interpreter = ticking_interpreter();
+
+        assert_eq!(run_with(&mut interpreter, "1 < tick(2) < 3").unwrap(), "true\n");
+        assert_eq!(take_ticks(), 1);
+
+        // The first comparison is false, so the last operand is never evaluated
+        assert_eq!(run_with(&mut interpreter, "3 < tick(2) < tick(4)").unwrap(), "false\n");
+        assert_eq!(take_ticks(), 1);
+
+        assert_eq!(run("1 < 2 > 0").unwrap(), "true\n");
+    }
+
+    #[test]
+    fn membership_short_circuits() {
+        let mut interpreter = ticking_interpreter();
+        run_with(&mut interpreter, "p(x) = tick(x) > 0").unwrap();
+
+        // 2 is in Nat, so the predicate isn't needed to decide it is in the union
+        assert_eq!(run_with(&mut interpreter, "2 : (Nat | setof(p))").unwrap(), "2\n");
+        assert_eq!(take_ticks(), 0);
+
+        // -1 isn't in Nat, so the predicate isn't needed to decide it isn't in the intersection
+        assert!(run_with(&mut interpreter, "-1 : (Nat & setof(p))").is_err());
+        assert_eq!(take_ticks(), 0);
+
+        assert!(run_with(&mut interpreter, "-1 : (Nat | setof(p))").is_err());
+        assert_eq!(take_ticks(), 1);
+    }
+
+    #[test]
+    fn operator_errors_name_the_operand_types() {
+        for (source, message) in [
+            ("\"a\" - 1", "Cannot apply '-' to Str and Int"),
+            ("() + 1.5", "Cannot apply '+' to Unit and Real"),
+            ("-\"a\"", "Cannot apply '-' to Str"),
+            ("true / \"b\"", "Cannot apply '/' to Bool and Str"),
+            ("[1] < 2i", "Cannot apply '<' to Tuple and Complex"),
+            ("2 ^ {1}", "Cannot apply '^' to Int and Set")
+        ] {
+            let Err(err) = run(source) else {
+                panic!("expected '{source}' to be an error")
+            };
+
+            assert!(err.to_string().starts_with(message), "unexpected error '{err}'");
+        }
+    }
+
+    #[test]
+    fn named_sets_are_types() {
+        let small = "Small = {1, 2, 3}\n";
+
+        assert_eq!(run(&format!("{small}x : Small = 2\nx")).unwrap().lines().last(), Some("2"));
+        assert_eq!(run(&format!("{small}f(a : Small) = a\nf(3)")).unwrap().lines().last(), Some("3"));
+
+        for (source, message) in [
+            ("y : Small = 4", "'4' is not an element of '{1, 2, 3}'"),
+            ("z : Small\nz = 5", "'z' is in '{1, 2, 3}' which does not contain '5'"),
+            ("f(a : Small) = a\nf(4)", "Parameter 'a' belongs to '{1, 2, 3}' which doesn't contain '4'")
+        ] {
+            let Err(err) = run(&format!("{small}{source}")) else {
+                panic!("expected '{source}' to be an error")
+            };
+
+            assert!(err.to_string().starts_with(message), "unexpected error '{err}'");
+        }
+    }
+
+    #[test]
+    fn number_literals_ignore_digit_separators() {
+        assert_eq!(run("1_000").unwrap(), "1000\n");
+        assert_eq!(run("1.2_5").unwrap(), "5/4\n");
+        assert_eq!(run("1_2.5e1_0 == 125 * 10^9").unwrap(), "true\n");
+    }
+
+    #[test]
+    fn bools_are_not_numbers() {
+        assert_eq!(run("typeof(true)").unwrap(), "Univ\n");
+
+        for source in ["x : Nat = true", "true : Real", "f(x : Nat) = x\nf(true)", "false : Int"] {
+            assert!(run(source).is_err(), "expected '{source}' to be an error");
+        }
+
+        // They are still 0 and 1 in arithmetic
+        assert_eq!(run("true + 1").unwrap(), "2\n");
+    }
+
+    #[test]
+    fn every_operator_coerces_alike() {
+        // Each value written as a bool or Int, and then as the same number of a wider kind
+        let operands = [("true", "1"), ("false", "0.0"), ("2", "2.0"), ("2", "2 + 0i"), ("1 / 2", "1 / 2 + 0i")];
+        let others = ["true", "3", "1.5", "1 + 2i"];
+
+        // Both sides must fail alike (like dividing by zero or an inexact power), or be equal
+        let agree = |left: &str, right: &str| {
+            assert_eq!(run(left).is_ok(), run(right).is_ok(), "only one of '{left}' and '{right}' failed");
+
+            if run(left).is_ok() {
+                assert_eq!(run(&format!("({left}) == ({right})")).unwrap(), "true\n", "'{left}' and '{right}' differ");
+            }
+        };
+
+        for operator in ['+', '-', '*', '/', '^'] {
+            for (narrow, wide) in operands {
+                // Complex non-integer exponents and roots of complex numbers aren't supported, so they can't agree
+                if operator == '^' && wide.ends_with('i') && narrow.contains('/') {
+                    continue;
+                }
+
+                for other in others {
+                    agree(&format!("({narrow}) {operator} ({other})"), &format!("({wide}) {operator} ({other})"));
+                    agree(&format!("({other}) {operator} ({narrow})"), &format!("({other}) {operator} ({wide})"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn casts_in_function_bodies() {
+        assert_eq!(run("f(x) = x : Real\n#{f, f}\nf(2)").unwrap().lines().skip(1).collect::<Vec<_>>(), ["1", "2"]);
+        assert_eq!(run("g(x, y) = (x + y) : Real\ng(1)\ng(1)(2)").unwrap().lines().skip(1).collect::<Vec<_>>(), ["y -> (1 + y) : Real", "3"]);
+    }
+
+    #[test]
+    fn assignment_in_function_body_is_an_error() {
+        let Err(err) = run("h(x) = (y = x)\nh(1)") else {
+            panic!("expected an error")
+        };
+
+        assert!(err.to_string().starts_with("'y = x' does not have a value"));
+        assert!(run("h(x, z) = (y = x)\nh(1)").is_err());
+    }
+
+    #[test]
+    fn finite_set_equality() {
+        assert_eq!(run("{1, 2} =: {2, 1}").unwrap(), "true\n");
+        assert_eq!(run("{1, 2} =: {1, 3}").unwrap(), "false\n");
+        assert_eq!(run("{1} =: {1.0}").unwrap(), "true\n");
+    }
+
+    #[test]
+    fn domain_and_codomain_of_functions() {
+        let declared = "f : Int -> Real\nf(x) = x / 2\n";
+
+        assert_eq!(run(&format!("{declared}domain(f) == [Int]\ncodomain(f) =: Real")).unwrap(), "f = x -> x / 2\ntrue\ntrue\n");
+        assert_eq!(run("g(x : Nat, y) = x\ndomain(g)").unwrap().lines().last(), Some("[Nat, Univ]"));
+
+        for source in ["domain(1)", "codomain({1})", "codomain(domain)"] {
+            assert!(run(source).is_err(), "expected '{source}' to be an error");
+        }
+    }
+
+    #[test]
+    fn negation_of_tuples_and_matrices() {
+        assert_eq!(run("-[1, -2, 3] == [-1, 2, -3]").unwrap(), "true\n");
+        assert_eq!(run("-[1, 2; 3, 4]").unwrap(), "[-1, -2; -3, -4]\n");
+        assert_eq!(run("-[]").unwrap(), "[]\n");
+
+        for source in ["-[\"a\"]", "-[1, [true, \"b\"]]"] {
+            assert!(run(source).is_err(), "expected '{source}' to be an error");
+        }
+    }
+
+    #[test]
+    fn scalar_division_of_tuples_and_matrices() {
+        assert_eq!(run("[2, 4; 6, 8] / 2 == [1, 2; 3, 4]").unwrap(), "true\n");
+        assert_eq!(run("[1, 2] / 4").unwrap(), "[1/4, 1/2]\n");
+
+        for (source, message) in [
+            ("[1, 2] / 0", "Cannot divide by '0'"),
+            ("2 / [1, 2]", "Cannot divide by '[1, 2]', because it is not a number"),
+            ("[1, 2] / [1, 2]", "'[1, 2]' can only be divided by a number, not '[1, 2]'")
+        ] {
+            let Err(err) = run(source) else {
+                panic!("expected '{source}' to be an error")
+            };
+
+            assert!(err.to_string().starts_with(message), "unexpected error '{err}'");
+        }
+    }
+
+    #[test]
+    fn operations_of_a_set_with_itself_are_canonical() {
+        for set in ["{1, 2}", "Nat", "Real", "Nat | {-1}"] {
+            let output = run(&format!("A = {set}\nA | A\nA & A\nA \\ A")).unwrap();
+
+            assert_eq!(output, format!("A = {set}\n{set}\n{set}\n{{}}\n"));
+            assert_eq!(run(&format!("A = {set}\n(A \\ A) =: Empty")).unwrap().lines().last(), Some("true"));
+        }
+    }
+
+    #[test]
+    fn finite_set_operations_display_their_elements() {
+        assert_eq!(run("{1, 2} | {2, 3}").unwrap(), "{1, 2, 3}\n");
+        assert_eq!(run("{1, 2} & {2, 3}").unwrap(), "{2}\n");
+        assert_eq!(run("{1, 2} \\ {2, 3}").unwrap(), "{1}\n");
+
+        // Infinite results are left as operations
+        assert_eq!(run("Nat | {5}").unwrap(), "Nat | {5}\n");
+        assert_eq!(run("({5} | Nat) =: (Nat | {5})").unwrap(), "true\n");
+    }
+
+    #[test]
+    fn disjoint_sets() {
+        for (source, expected) in [
+            ("disjoint({1, 2}, {3, 4})", "true"),
+            ("disjoint({1, 2}, {2, 3})", "false"),
+            ("disjoint({-1, \"a\"}, Nat)", "true"),
+            ("disjoint(Nat, Str)", "true"),
+            ("disjoint(Nat, Int)", "false")
+        ] {
+            assert_eq!(run(source).unwrap(), format!("{expected}\n"), "running '{source}'");
+        }
+
+        let Err(err) = run("p(x) = true\ndisjoint(setof(p), Nat)") else {
+            panic!("expected disjointness from a predicate to be undecidable")
+        };
+
+        assert!(err.to_string().starts_with("Cannot decide whether 'setof(x -> true)' and 'Nat' are disjoint"));
+        assert!(run("disjoint({1}, 2)").is_err());
+    }
+
+    #[test]
+    fn finite_symmetric_difference_is_computed() {
+        assert_eq!(run("{1, 2, 3} ~ {2, 3, 4}").unwrap(), "{1, 4}\n");
+        assert_eq!(run("{1, 2, 3} ~ {2, 3, 4} == {1, 4}").unwrap(), "true\n");
+
+        // Equal numbers of different kinds are the same element
+        assert_eq!(run("{1, 2} ~ {2.0, 1}").unwrap(), "{}\n");
+        assert_eq!(run("Nat ~ {1}").unwrap(), "Nat ~ {1}\n");
+    }
+
+    #[test]
+    fn unary_plus_is_the_identity_on_numbers() {
+        for (source, expected) in [("+5", "5"), ("+(3 + 4i)", "3+4i"), ("+(1 / 2)", "1/2"), ("+[1, 2]", "[1, 2]")] {
+            assert_eq!(run(source).unwrap(), format!("{expected}\n"), "running '{source}'");
+        }
+
+        assert_eq!(run("+5 == 5").unwrap(), "true\n");
+        assert_eq!(run("+(3+4i) == 3+4i").unwrap(), "true\n");
+
+        for source in ["+\"a\"", "+{1}"] {
+            assert!(run(source).is_err(), "expected '{source}' to be an error");
+        }
+    }
+
+    #[test]
+    fn unit_is_a_value() {
+        // Unit isn't echoed on its own, but shows as `()` elsewhere
+        assert_eq!(run("()").unwrap(), "");
+        assert_eq!(run("unit").unwrap(), "");
+        assert_eq!(run("[(), unit]").unwrap(), "[(), ()]\n");
+        assert_eq!(run("() == unit").unwrap(), "true\n");
+
+        // Statements without a meaningful result give unit
+        assert_eq!(run("x = do end").unwrap(), "x = ()\n");
+        assert_eq!(run("x = assert(true)").unwrap(), "x = ()\n");
+        assert_eq!(run("f(x) = ()\n[f(1)]").unwrap().lines().last(), Some("[()]"));
+    }
+
+    #[test]
+    fn chained_comparison_tests_an_interval() {
+        let mut interpreter = ticking_interpreter();
+
+        for (x, expected) in [("5", "true"), ("15", "false"), ("0", "true"), ("10", "true"), ("-1/2", "false")] {
+            assert_eq!(run_with(&mut interpreter, &format!("0 <= tick({x}) <= 10")).unwrap(), format!("{expected}\n"), "checking if '{x}' is between 0 and 10");
+            assert_eq!(take_ticks(), 1);
+        }
+
+        assert_eq!(run("0 <= 5 <= 10").unwrap(), "true\n");
+        assert_eq!(run("0 <= 15 <= 10").unwrap(), "false\n");
+    }
+
+    #[test]
+    fn number_before_e_without_digits_is_multiplied() {
+        assert_eq!(run("e = 3\n2e").unwrap(), "e = 3\n6\n");
+        assert_eq!(run("e = 3\n2 e").unwrap(), "e = 3\n6\n");
+        assert_eq!(run("e10 = 3\n2e10").unwrap(), "e10 = 3\n20000000000\n");
+    }
+
+    #[test]
+    fn exclusion_by_a_superset_is_empty() {
+        for source in ["{1, 2} \\ {1, 2, 3}", "{1, 2} \\ Nat", "{1, 2} \\ (Int | {\"a\"})"] {
+            assert_eq!(run(source).unwrap(), "{}\n", "running '{source}'");
+            assert_eq!(run(&format!("({source}) == Empty")).unwrap(), "true\n", "running '{source}'");
+        }
+
+        assert_eq!(run("{-1, 2} \\ Nat").unwrap(), "{-1}\n");
+    }
+
+    #[test]
+    fn set_builder_with_several_binders() {
+        assert_eq!(run("{ [x, y] | x : {1, 2}, y : {3, 4} }").unwrap(), "{[1, 3], [1, 4], [2, 3], [2, 4]}\n");
+        assert_eq!(run("{ [x, y] | x : {1, 2}, y : {3, 4} & x + y == 5 }").unwrap(), "{[1, 4], [2, 3]}\n");
+
+        let Err(err) = run("{ [x, y] | x : {1, 2}, y : Nat }") else {
+            panic!("expected an infinite source to be an error")
+        };
+
+        assert!(err.to_string().starts_with("Cannot build a set from 'Nat', because it is infinite"));
+    }
+
+    #[test]
+    fn inline_argument_types_are_checked_at_call_time() {
+        let f = "f(x : Nat, y : Int) = x + y\n";
+
+        assert_eq!(run(&format!("{f}f(1, -2)")).unwrap(), "f = (x, y) -> x + y\n-1\n");
+        assert_eq!(run(&format!("{f}domain(f)")).unwrap().lines().last(), Some("[Nat, Int]"));
+
+        for (call, message) in [
+            ("f(-1, 2)", "Parameter 'x' belongs to 'Nat' which doesn't contain '-1'"),
+            ("f(1, 1/2)", "Parameter 'y' belongs to 'Int' which doesn't contain '1/2'")
+        ] {
+            let Err(err) = run(&format!("{f}{call}")) else {
+                panic!("expected '{call}' to be an error")
+            };
+
+            assert!(err.to_string().starts_with(message), "unexpected error '{err}'");
+        }
+    }
+
+    #[test]
+    fn typed_assignment_errors_depend_on_the_set() {
+        for (source, message) in [
+            ("x : {1, 2, 3} = 5", "'5' is not an element of '{1, 2, 3}'"),
+            ("x : {1, 2} | {3} = 5", "'5' is not an element of '{1, 2, 3}'"),
+            ("x : Nat = -1", "'-1' is not in 'Nat'"),
+            ("x : Nat | {-2} = -1", "'-1' is not in 'Nat | {-2}'")
+        ] {
+            let Err(err) = run(source) else {
+                panic!("expected '{source}' to be an error")
+            };
+
+            assert!(err.to_string().starts_with(message), "unexpected error '{err}'");
+        }
+    }
+
+    #[test]
+    fn set_operators_group_from_the_left() {
+        for (source, expected) in [
+            ("{1, 2, 3} \\ {2} | {4}", "{1, 3, 4}"),
+            ("{1, 2, 3} \\ ({2} | {4})", "{1, 3}"),
+            ("{1, 2} | {2, 3} & {3}", "{3}"),
+            ("{1, 2} & {2, 3} | {5}", "{2, 5}"),
+            ("{1} | {2} == {1, 2}", "true")
+        ] {
+            assert_eq!(run(source).unwrap(), format!("{expected}\n"), "running '{source}'");
+        }
+
+        let Err(err) = run("1 + {2}") else {
+            panic!("expected adding a set to be an error")
+        };
+
+        assert!(err.to_string().starts_with("Cannot apply arithmetic operator '+' to the set '{2}', use '|' for a union"));
     }
 }
@@ -1,27 +1,71 @@
-use crate::{set::{FiniteSet, Set}, value::Val};
+use num::{BigInt, One, Signed, Zero};
 
-pub trait ValIterator {
+use crate::{set::FiniteSet, value::Val};
+
+/// An iterator over the values of an enumerable set.
+pub trait ValIterator: Iterator<Item = Box<dyn Val>> {
     
 }
 
-impl Iterator for dyn ValIterator {
+/// Iterates over a finite set's elements in the order they are displayed, so the same set always enumerates the same way.
+pub struct FiniteIterator {
+    elements: std::vec::IntoIter<Box<dyn Val>>
+}
+
+impl From<&FiniteSet> for FiniteIterator {
+    fn from(value: &FiniteSet) -> Self {
+        Self {
+            elements: value.sorted().into_iter().map(|element| element.clone_box()).collect::<Vec<_>>().into_iter()
+        }
+    }
+}
+
+impl Iterator for FiniteIterator {
     type Item = Box<dyn Val>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        todo!()
+        self.elements.next()
     }
 }
 
-pub struct FiniteIterator {
-    
+impl ValIterator for FiniteIterator {}
+
+/// Lazily counts through the integers, never ending: from `0, 1, 2, ...` for `Nat`, or `0, 1, -1, 2, -2, ...` for `Int`, so every integer is reached.
+pub struct InfiniteIterator {
+    next: BigInt,
+    signed: bool
 }
 
-impl From<FiniteSet> for FiniteIterator {
-    fn from(value: FiniteSet) -> Self {
-        todo!()
+impl InfiniteIterator {
+    pub fn nat() -> Self {
+        Self {
+            next: BigInt::zero(),
+            signed: false
+        }
+    }
+
+    pub fn int() -> Self {
+        Self {
+            next: BigInt::zero(),
+            signed: true
+        }
     }
 }
 
-pub struct InfiniteIterator {
+impl Iterator for InfiniteIterator {
+    type Item = Box<dyn Val>;
 
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.clone();
+
+        self.next = if self.signed && current.is_positive() {
+            -&current
+        } else {
+            current.abs() + BigInt::one()
+        };
+
+        Some(Box::new(current))
+    }
 }
+
+impl ValIterator for InfiniteIterator {}
@@ -1,21 +1,27 @@
-use std::io::{BufRead, BufReader, Read};
+use std::io::{self, BufRead, BufReader, Read};
 
 use crate::{error::{self, Error, LexError}, token::{Token, TokenKind}};
 
 pub struct Lexer<'t> {
-    src: Box<dyn Iterator<Item = String> + 't>,
+    src: Box<dyn Iterator<Item = io::Result<String>> + 't>,
     line: usize,
     in_string: bool,
-    comment_nest_lvl: u32
+    raw_string: bool,
+    string_start: (usize, usize),
+    comment_nest_lvl: u32,
+    comment_start: (usize, usize)
 }
 
 impl<'t> Lexer<'t> {
     pub fn new<R: Read + 't>(src: R) -> Self {
         Self {
-            src: Box::new(BufReader::new(src).lines().map(Result::unwrap)),
+            src: Box::new(BufReader::new(src).lines()),
             line: 1,
             in_string: false,
-            comment_nest_lvl: 0
+            raw_string: false,
+            string_start: (0, 0),
+            comment_nest_lvl: 0,
+            comment_start: (0, 0)
         }
     }
 
@@ -23,19 +29,28 @@ impl<'t> Lexer<'t> {
         let mut tokens = vec![];
         
         while let Some(line) = self.src.next() {
+            let line = line?;
             self.lex_line(&mut tokens, &line);
 
             if !self.in_string && self.comment_nest_lvl == 0 {
-                self.add_token(&mut tokens, TokenKind::EOL);
+                self.add_token(&mut tokens, TokenKind::EOL, line.chars().count() + 1);
             }
 
             self.line += 1;
         }
 
-        self.add_token(&mut tokens, TokenKind::EOF);
+        self.add_token(&mut tokens, TokenKind::EOF, 1);
 
         if self.in_string {
-            return Err(Error::Lexer(LexError::UnclosedString));
+            let (line, col) = self.string_start;
+
+            return Err(Error::Lexer(LexError::UnclosedString { line, col }));
+        }
+
+        if self.comment_nest_lvl > 0 {
+            let (line, col) = self.comment_start;
+
+            return Err(Error::Lexer(LexError::UnclosedComment { line, col }));
         }
 
         Ok(tokens)
@@ -47,7 +62,20 @@ impl<'t> Lexer<'t> {
         let mut current = chars.next();
         let mut current_token = Token::default(); // Placeholder value
 
-        while let Some(ch) = current.clone() {
+        while let Some(ch) = current {
+            let col = i + 1;
+
+            // An `e` right after a number's digits starts an exponent only if digits (perhaps signed) follow, so `2e10` is a number but `2e` is `2` and then `e`
+            let starts_exponent = matches!(ch, 'e' | 'E') && matches!(current_token.kind(), TokenKind::Number(n) if !n.contains(['e', 'E'])) && {
+                let mut ahead = chars.clone();
+
+                match ahead.next() {
+                    Some('+' | '-') => ahead.next().is_some_and(|ch| ch.is_ascii_digit()),
+                    Some(ch) => ch.is_ascii_digit(),
+                    None => false
+                }
+            };
+
             let mut next = || {
                 current = chars.next();
                 i += 1;
@@ -59,39 +87,64 @@ impl<'t> Lexer<'t> {
                 self.line += 1;
             }
 
+            // Inside a comment only `/*` and `*/` matter, so `/* a /* b */ c */` is a single comment.
+            // If the character after this one doesn't complete either, it is looked at again on its own.
             if self.comment_nest_lvl > 0 {
-                if ch == '*' {
-                    if let Some('/') = next() {
+                match (ch, next()) {
+                    ('*', Some('/')) => {
                         self.comment_nest_lvl -= 1;
-
                         next();
                     }
-
-                    continue;
-                } else if ch == '/' {
-                    if let Some('*') = next() {
+                    ('/', Some('*')) => {
                         self.comment_nest_lvl += 1;
-
                         next();
                     }
-                } else {
-                    next();
+                    _ => ()
                 }
 
-                println!("cc [{}]", ch);
+                continue;
+            }
+
+            // A string only ends at the kind of quote that opened it, so "don't" and '"' are fine
+            let ends_string = match current_token.kind() {
+                TokenKind::String(_) => ch == '"',
+                TokenKind::Char(_) => ch == '\'',
+                _ => ['\'', '"'].contains(&ch)
+            };
 
+            if self.in_string && ch == '\\' && !self.raw_string {
+                // Anything that isn't an escape sequence keeps its backslash
+                match next().map(|escaped| (escaped, Self::unescape(escaped))) {
+                    Some((_, Some(unescaped))) => current_token.append_to_lexeme(unescaped),
+                    Some((escaped, None)) => {
+                        current_token.append_to_lexeme('\\');
+                        current_token.append_to_lexeme(escaped);
+                    }
+                    None => current_token.append_to_lexeme('\\')
+                }
 
+                next();
                 continue;
             }
 
-            if self.in_string && !['\'', '"'].contains(&ch) {
+            if self.in_string && !ends_string {
                 current_token.append_to_lexeme(ch);
                 next();
                 continue;
-                // todo escape sequences
             } 
+
+            if starts_exponent {
+                current_token.append_to_lexeme(ch);
+
+                if let Some(sign @ ('+' | '-')) = next() {
+                    current_token.append_to_lexeme(sign);
+                    next();
+                }
+
+                continue;
+            }
             
-            if matches!(current_token.kind(), TokenKind::Number(_)) && !(ch.is_digit(10) || ch == '_') {
+            if matches!(current_token.kind(), TokenKind::Number(_)) && !(ch.is_ascii_digit() || ch == '_') {
                 tokens.push(current_token);
                 current_token = Token::default();
             } 
@@ -102,20 +155,20 @@ impl<'t> Lexer<'t> {
             }
 
             match ch {
-                '+' => self.add_token(tokens, TokenKind::Plus),
+                '+' => self.add_token(tokens, TokenKind::Plus, col),
                 '-' => {
                     let n = next();
                     
                     if let Some('>') = n {
-                        self.add_token(tokens, TokenKind::SmallArrow);
+                        self.add_token(tokens, TokenKind::SmallArrow, col);
                     } else {
-                        self.add_token(tokens, TokenKind::Minus);
+                        self.add_token(tokens, TokenKind::Minus, col);
                         
                         continue;
                     }
                 }
                 '*' => {
-                    self.add_token(tokens, TokenKind::Star)
+                    self.add_token(tokens, TokenKind::Star, col)
                 },
                 '/' => {
                     let n = next();
@@ -136,89 +189,115 @@ impl<'t> Lexer<'t> {
                         }
 
                         self.comment_nest_lvl += 1;
+                        self.comment_start = (self.line, col);
 
                         next();
                         continue;
                     }
 
-                    self.add_token(tokens, TokenKind::Slash);
+                    self.add_token(tokens, TokenKind::Slash, col);
                     continue;
                 }
-                '^' => self.add_token(tokens, TokenKind::Caret),
+                '^' => self.add_token(tokens, TokenKind::Caret, col),
                 '=' => {
                     let n = next();
 
                     if let Some('=') = n {
-                        self.add_token(tokens, TokenKind::DblEq);
+                        self.add_token(tokens, TokenKind::DblEq, col);
                     } else if let Some(':') = n {
-                        self.add_token(tokens, TokenKind::EqColon);
+                        self.add_token(tokens, TokenKind::EqColon, col);
                     } else if let Some('>') = n {
-                        self.add_token(tokens, TokenKind::FatArrow);
+                        self.add_token(tokens, TokenKind::FatArrow, col);
                     } else {
-                        self.add_token(tokens, TokenKind::Eq);
+                        self.add_token(tokens, TokenKind::Eq, col);
                         continue;
                     }
                 }
                 '!' => {
                     if let Some('=') = next() {
-                        self.add_token(tokens, TokenKind::BangEq);
+                        self.add_token(tokens, TokenKind::BangEq, col);
                     } else {
-                        self.add_token(tokens, TokenKind::Bang);
+                        self.add_token(tokens, TokenKind::Bang, col);
                         continue;
                     }
                 }
-                '~' => self.add_token(tokens, TokenKind::Tilde),
+                '~' => self.add_token(tokens, TokenKind::Tilde, col),
                 '|' => {
                     if let Some('|') = next() {
-                        self.add_token(tokens, TokenKind::DblBar);
+                        self.add_token(tokens, TokenKind::DblBar, col);
                     } else {
-                        self.add_token(tokens, TokenKind::Bar);
+                        self.add_token(tokens, TokenKind::Bar, col);
                         continue;
                     }
                 },
                 '&' => {
                     if let Some('&') = next() {
-                        self.add_token(tokens, TokenKind::DblAmp);
+                        self.add_token(tokens, TokenKind::DblAmp, col);
                     } else {
-                        self.add_token(tokens, TokenKind::Amp);
+                        self.add_token(tokens, TokenKind::Amp, col);
                         continue;
                     }
                 }
-                '\\' => self.add_token(tokens, TokenKind::BackSlash),
+                '\\' => self.add_token(tokens, TokenKind::BackSlash, col),
                 '<' => {
-                    if let Some(':') = next() {
-                        self.add_token(tokens, TokenKind::LessColon);
+                    let n = next();
+
+                    if let Some(':') = n {
+                        self.add_token(tokens, TokenKind::LessColon, col);
+                    } else if let Some('=') = n {
+                        if let Some(':') = next() {
+                            self.add_token(tokens, TokenKind::LessEqColon, col);
+                        } else {
+                            self.add_token(tokens, TokenKind::LessEq, col);
+                            continue;
+                        }
                     } else {
-                        self.add_token(tokens, TokenKind::Less);
+                        self.add_token(tokens, TokenKind::Less, col);
                         continue;
                     }
                 }
                 '>' => {
-                    if let Some(':') = next() {
-                        self.add_token(tokens, TokenKind::GreaterColon);
+                    let n = next();
+
+                    if let Some(':') = n {
+                        self.add_token(tokens, TokenKind::GreaterColon, col);
+                    } else if let Some('=') = n {
+                        if let Some(':') = next() {
+                            self.add_token(tokens, TokenKind::GreaterEqColon, col);
+                        } else {
+                            self.add_token(tokens, TokenKind::GreaterEq, col);
+                            continue;
+                        }
+                    } else {
+                        self.add_token(tokens, TokenKind::Greater, col);
+                        continue;
+                    }
+                }
+                '(' => self.add_token(tokens, TokenKind::OpenParen, col),
+                ')' => self.add_token(tokens, TokenKind::CloseParen, col),
+                '[' => self.add_token(tokens, TokenKind::OpenBracket, col),
+                ']' => self.add_token(tokens, TokenKind::CloseBracket, col),
+                '{' => self.add_token(tokens, TokenKind::OpenBrace, col),
+                '}' => self.add_token(tokens, TokenKind::CloseBrace, col),
+                ',' => self.add_token(tokens, TokenKind::Comma, col),
+                '.' => {
+                    if let Some('.') = next() {
+                        self.add_token(tokens, TokenKind::DblDot, col);
                     } else {
-                        self.add_token(tokens, TokenKind::Greater);
+                        self.add_token(tokens, TokenKind::Dot, col);
                         continue;
                     }
                 }
-                '(' => self.add_token(tokens, TokenKind::OpenParen),
-                ')' => self.add_token(tokens, TokenKind::CloseParen),
-                '[' => self.add_token(tokens, TokenKind::OpenBracket),
-                ']' => self.add_token(tokens, TokenKind::CloseBracket),
-                '{' => self.add_token(tokens, TokenKind::OpenBrace),
-                '}' => self.add_token(tokens, TokenKind::CloseBrace),
-                ',' => self.add_token(tokens, TokenKind::Comma),
-                '.' => self.add_token(tokens, TokenKind::Dot),
-                ';' => self.add_token(tokens, TokenKind::Semicolon),
-                ':' => self.add_token(tokens, TokenKind::Colon),
-                '#' => self.add_token(tokens, TokenKind::Hash),
-                '\n' => self.add_token(tokens, TokenKind::EOL),
+                ';' => self.add_token(tokens, TokenKind::Semicolon, col),
+                ':' => self.add_token(tokens, TokenKind::Colon, col),
+                '#' => self.add_token(tokens, TokenKind::Hash, col),
+                '\n' => self.add_token(tokens, TokenKind::EOL, col),
                 '_' => match current_token.kind() {
                     TokenKind::Ident(_)  => current_token.append_to_lexeme(ch),
                     TokenKind::Number(_) => current_token.append_to_lexeme(ch),
                     TokenKind::String(_) |
                     TokenKind::Char(_)   => unreachable!(),
-                    _ => current_token = Token::new(TokenKind::Ident("_".to_owned()), self.line),
+                    _ => current_token = Token::new(TokenKind::Ident("_".to_owned()), self.line, col),
                 },
                 '\'' => {
                     if let TokenKind::Char(_) = current_token.kind() {
@@ -226,8 +305,9 @@ impl<'t> Lexer<'t> {
                         current_token = Token::default();
                         self.in_string = false;
                     } else {
-                        current_token = Token::new(TokenKind::Char(String::new()), self.line);
+                        current_token = Token::new(TokenKind::Char(String::new()), self.line, col);
                         self.in_string = true;
+                        self.string_start = (self.line, col);
                     }
                 }
                 '"' => {
@@ -236,24 +316,32 @@ impl<'t> Lexer<'t> {
                         current_token = Token::default();
                         self.in_string = false;
                     } else {
-                        current_token = Token::new(TokenKind::String(String::new()), self.line);
+                        // `r` right before the quote makes it a raw string, in which backslashes are just backslashes
+                        let is_raw = tokens.last().is_some_and(|prev| 
+                            prev.kind() == &TokenKind::Ident("r".to_owned()) && prev.line() == self.line && prev.col() + 1 == col
+                        );
+                        let start = if is_raw { tokens.pop().map_or(col, |prev| prev.col()) } else { col };
+
+                        current_token = Token::new(TokenKind::String(String::new()), self.line, start);
                         self.in_string = true;
+                        self.raw_string = is_raw;
+                        self.string_start = (self.line, start);
                     }
                 },
                 _ => {
-                    if ch.is_digit(10) {
+                    if ch.is_ascii_digit() {
                         if let TokenKind::Number(_) = current_token.kind() {
                             current_token.append_to_lexeme(ch);
                         } else if let TokenKind::Ident(_) = current_token.kind() {
                             current_token.append_to_lexeme(ch);
                         } else {
-                            current_token = Token::new(TokenKind::Number(String::from(ch)), self.line);
+                            current_token = Token::new(TokenKind::Number(String::from(ch)), self.line, col);
                         }
                     } else if ch.is_alphabetic() {
                         if let TokenKind::Ident(_) = current_token.kind() {
                             current_token.append_to_lexeme(ch);
                         } else  {
-                            current_token = Token::new(TokenKind::Ident(String::from(ch)), self.line);
+                            current_token = Token::new(TokenKind::Ident(String::from(ch)), self.line, col);
                         }
                     }
                 }
@@ -268,7 +356,98 @@ impl<'t> Lexer<'t> {
     }
 
 
-    pub fn add_token(&self, tokens: &mut Vec<Token>, kind: TokenKind) {
-        tokens.push(Token::new(kind, self.line));
+    /// The character that `\` followed by `ch` stands for in a string, if it is an escape sequence.
+    fn unescape(ch: char) -> Option<char> {
+        match ch {
+            'n' => Some('\n'),
+            't' => Some('\t'),
+            'r' => Some('\r'),
+            '0' => Some('\0'),
+            '\\' | '\'' | '"' => Some(ch),
+            _ => None
+        }
+    }
+
+    pub fn add_token(&self, tokens: &mut Vec<Token>, kind: TokenKind, col: usize) {
+        tokens.push(Token::new(kind, self.line, col));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<TokenKind> {
+        Lexer::new(source.as_bytes()).lex().unwrap().iter().map(|token| token.kind().clone()).collect()
+    }
+
+    #[test]
+    fn longest_operator_is_lexed() {
+        assert_eq!(kinds("a <=: b"), vec![
+            TokenKind::Ident(String::from("a")),
+            TokenKind::LessEqColon,
+            TokenKind::Ident(String::from("b")),
+            TokenKind::EOL,
+            TokenKind::EOF
+        ]);
+        assert_eq!(kinds("x->1..2")[1..5], [
+            TokenKind::SmallArrow,
+            TokenKind::Number(String::from("1")),
+            TokenKind::DblDot,
+            TokenKind::Number(String::from("2"))
+        ]);
+    }
+
+    #[test]
+    fn numbers_and_strings() {
+        // The parser joins the parts of a decimal, so the lexer keeps them apart
+        assert_eq!(kinds("1.5 2e3 \"a b\"")[..5], [
+            TokenKind::Number(String::from("1")),
+            TokenKind::Dot,
+            TokenKind::Number(String::from("5")),
+            TokenKind::Number(String::from("2e3")),
+            TokenKind::String(String::from("a b"))
+        ]);
+    }
+
+    #[test]
+    fn tokens_know_their_position() {
+        let tokens = Lexer::new("x = 1\n  y".as_bytes()).lex().unwrap();
+        let y = tokens.iter().find(|token| token.lexeme() == "y").unwrap();
+
+        assert_eq!((y.line(), y.col()), (2, 3));
+    }
+
+    #[test]
+    fn unclosed_string_is_an_error() {
+        assert!(matches!(
+            Lexer::new("x = \"abc".as_bytes()).lex(),
+            Err(Error::Lexer(LexError::UnclosedString { line: 1, col: 5 }))
+        ));
+    }
+
+    #[test]
+    fn e_is_an_exponent_only_before_digits() {
+        let number = |lexeme: &str| TokenKind::Number(String::from(lexeme));
+        let e = TokenKind::Ident(String::from("e"));
+
+        assert_eq!(kinds("2e10")[..1], [number("2e10")]);
+        assert_eq!(kinds("2E+3 2e-1")[..2], [number("2E+3"), number("2e-1")]);
+        assert_eq!(kinds("2e")[..2], [number("2"), e.clone()]);
+        assert_eq!(kinds("2 e")[..2], [number("2"), e.clone()]);
+        assert_eq!(kinds("2e-")[..3], [number("2"), e, TokenKind::Minus]);
+        assert_eq!(kinds("2ex")[..2], [number("2"), TokenKind::Ident(String::from("ex"))]);
+    }
+
+    #[test]
+    fn raw_strings_keep_backslashes() {
+        let string = |value: &str| TokenKind::String(String::from(value));
+
+        assert_eq!(kinds(r#"r"a\nb""#)[..1], [string("a\\nb")]);
+        assert_eq!(kinds(r#""a\nb""#)[..1], [string("a\nb")]);
+        assert_eq!(kinds(r#"r"C:\path\no\escapes""#)[..1], [string("C:\\path\\no\\escapes")]);
+
+        // An `r` with a space before the quote is just a name
+        assert_eq!(kinds(r#"r "a""#)[..2], [TokenKind::Ident(String::from("r")), string("a")]);
     }
 }
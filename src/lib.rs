@@ -0,0 +1,14 @@
+pub mod ast;
+pub mod builtins;
+pub mod config;
+pub mod diagnostic;
+pub mod environment;
+pub mod error;
+pub mod interpreter;
+pub mod iter;
+pub mod lexer;
+pub mod parser;
+pub mod set;
+pub mod token;
+mod types;
+pub mod value;
@@ -1,44 +1,138 @@
-mod ast;
-mod config;
-mod environment;
-mod error;
-mod interpreter;
-mod iter;
-mod lexer;
-mod parser;
-mod set;
-mod token;
-mod types;
-mod value;
-
-use std::env;
-
-use config::{Config, Mode};
-use interpreter::Interpreter;
-use lexer::Lexer;
-use parser::Parser;
+use std::{env, fs, io::{self, BufRead, Write}, process};
+
+use math_lang::config::{Config, Mode};
+use math_lang::diagnostic;
+use math_lang::environment::SymKind;
+use math_lang::error::{self, Error};
+use math_lang::interpreter::Interpreter;
+use math_lang::lexer::Lexer;
+use math_lang::parser::Parser;
 
 fn main() {
-    let config = Config::build(env::args()).unwrap();
+    let config = Config::build(env::args()).unwrap_or_else(|err| exit_with(&Error::Io(err), None));
 
-    if let Mode::File(path) = config.mode() {
-        let stuff = std::fs::File::open(path).unwrap();
-        let mut lexer = Lexer::new(stuff);
+    match config.mode() {
+        Mode::Files(paths) => {
+            let mut interpreter = configured_interpreter(&config);
 
-        let tokens = lexer.lex().unwrap();
+            for path in paths {
+                let source = fs::read_to_string(path).unwrap_or_else(|err| {
+                    exit_with(&Error::Io(io::Error::new(err.kind(), format!("cannot open '{path}': {err}"))), None)
+                });
 
-        println!("\n--- Tokens ---\n{:#?}", tokens);
+                if let Err(err) = run(&config, &mut interpreter, &source) {
+                    exit_with(&err, Some(&source));
+                }
+            }
+        }
+        Mode::Eval(source) => if let Err(err) = run(&config, &mut configured_interpreter(&config), source) {
+            exit_with(&err, Some(source));
+        }
+        Mode::Repl => if let Err(err) = repl(&config) {
+            exit_with(&Error::Io(err), None);
+        }
+    }
+}
 
-        let mut parser = Parser::new(&tokens);
-        let ast = parser.parse();
+/// Prints `err`, and exits with a nonzero code.
+fn exit_with(err: &Error, source: Option<&str>) -> ! {
+    report(err, source);
+    process::exit(1);
+}
 
-        println!("\n--- AST---\n{:#?}", ast);
+/// Prints `err`, pointing into `source` when the error has a position.
+fn report(err: &Error, source: Option<&str>) {
+    let message = format!("error: {err}");
 
-        println!("\n--- Code Output ---");
+    match (source, err.position()) {
+        (Some(source), Some((line, col))) => eprintln!("{}", diagnostic::render(source, line, col, &message)),
+        _ => eprintln!("{message}")
+    }
+}
 
-        let mut interpreter = Interpreter::new();
-        interpreter.interpret(ast.stmts());
+/// Runs a program, showing only its output (and its syntax tree, with `--ast`).
+fn run(config: &Config, interpreter: &mut Interpreter, source: &str) -> error::Result<()> {
+    let tokens = Lexer::new(source.as_bytes()).lex()?;
+    let ast = Parser::new(&tokens).parse()?;
 
-        println!("\n--- Interpreter State ---\n{:#?}", interpreter);
+    if config.dump_ast() {
+        print!("{ast}");
     }
+
+    interpreter.interpret(ast.stmts())
+}
+
+/// Reads, executes, and echoes one line at a time, keeping the same environment between lines until `:quit` or the end of input. `:env` lists what is defined.
+fn repl(config: &Config) -> io::Result<()> {
+    let mut interpreter = configured_interpreter(config);
+
+    let mut stdout = io::stdout();
+    let mut lines = io::stdin().lock().lines();
+
+    loop {
+        print!("> ");
+        stdout.flush()?;
+
+        let Some(line) = lines.next() else {
+            println!();
+            break;
+        };
+        let line = line?;
+
+        match line.trim() {
+            ":quit" => break,
+            ":env" => {
+                print_env(&interpreter);
+                continue;
+            }
+            "" => continue,
+            _ => ()
+        }
+
+        let tokens = match Lexer::new(line.as_bytes()).lex() {
+            Ok(tokens) => tokens,
+            Err(err) => {
+                report(&err, Some(&line));
+                continue;
+            }
+        };
+
+        let ast = match Parser::new(&tokens).parse() {
+            Ok(ast) => ast,
+            Err(err) => {
+                report(&err, Some(&line));
+                continue;
+            }
+        };
+
+        if let Err(err) = interpreter.interpret(ast.stmts()) {
+            report(&err, Some(&line));
+        }
+    }
+
+    Ok(())
+}
+
+/// Lists each top-level binding, builtins included, as it would be written.
+fn print_env(interpreter: &Interpreter) {
+    for (name, kind) in interpreter.bindings() {
+        match kind {
+            SymKind::Value(value) => println!("{name} = {value}"),
+            SymKind::Type(typeset) | SymKind::FuncType(typeset) => println!("{name} : {typeset}")
+        }
+    }
+}
+
+/// Creates an interpreter with the settings given on the command line.
+fn configured_interpreter(config: &Config) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+
+    if let Some(max) = config.max_set_size() {
+        interpreter.set_max_set_size(max);
+    }
+
+    interpreter.set_strict_equality(config.strict_equality());
+    interpreter.set_exact(config.exact());
+
+    interpreter
 }
@@ -1,52 +1,120 @@
-use core::panic;
-use num::{BigInt, BigRational, Complex, Zero};
+use num::{BigInt, BigRational, Complex, One, Zero};
+use num::pow::Pow;
 
 use crate::ast::{Ast, expr::*, stmt::*};
+use crate::error::{self, Error, ParseError};
 use crate::token::{Token, TokenKind};
+use crate::value::Unit;
+
+/// The type of each argument of a function definition, if it was given inline.
+type ArgTypes = Vec<Option<Box<dyn Expr>>>;
 
 pub struct Parser<'t> {
     tokens: &'t [Token],
-    line: usize,
     i: usize,
 }
 
 impl<'t> Parser<'t> {
     const KEYWORDS: [&'static str; 8] = ["do", "end", "data", "class", "object", "import", "as", "proc"];
 
+    /// The largest exponent (in either direction) a number literal like `1e10` can have.
+    const MAX_EXPONENT: u64 = 100_000;
+
     pub fn new(tokens: &'t [Token]) -> Self {
         Self { 
             tokens, 
-            line: 0,
             i: 0
         }
     }
 
-    pub fn parse(&mut self) -> Ast {
+    pub fn parse(&mut self) -> error::Result<Ast> {
         let mut ast = Ast::new();
 
         while self.current().kind() != &TokenKind::EOF {
             if let TokenKind::EOL | TokenKind::Semicolon = self.current().kind() {
-                if let TokenKind::EOL = self.current().kind() {
-                    self.line += 1;
-                }
-                
                 self.next();
                 continue;
             }
 
-            ast.push_stmt(self.parse_stmt());
+            ast.push_stmt(self.parse_stmt()?);
             self.next();
         }
 
-        ast
+        Ok(ast)
+    }
+
+    fn parse_stmt(&mut self) -> error::Result<Box<dyn Stmt>> {        
+        if self.current().kind() == &TokenKind::Ident("do".to_owned()) {
+            self.parse_block()
+        } else if self.current().kind() == &TokenKind::Ident("import".to_owned()) {
+            self.parse_import()
+        } else {
+            self.parse_expr_stmt()
+        }
+    }
+
+    /// Parses a `do ... end` block as a statement, starting at `do`.
+    fn parse_block(&mut self) -> error::Result<Box<dyn Stmt>> {
+        let stmts = self.parse_block_stmts()?;
+
+        if self.match_next(&[&TokenKind::EOL, &TokenKind::Semicolon]) || self.peek_kind() == Some(&TokenKind::EOF) {
+            Ok(Box::new(Block(stmts)))
+        } else {
+            self.error("Expected ';' or EOL after 'end'")
+        }
+    }
+
+    /// Parses the statements of a `do ... end` block, from `do` until `end`.
+    fn parse_block_stmts(&mut self) -> error::Result<Vec<Box<dyn Stmt>>> {
+        let mut stmts = vec![];
+        self.next();
+
+        loop {
+            match self.current().kind() {
+                TokenKind::EOL | TokenKind::Semicolon => self.next(),
+                TokenKind::EOF => return self.error("Expected 'end' to close 'do' block"),
+                TokenKind::Ident(lexeme) if lexeme == "end" => break,
+                _ => {
+                    stmts.push(self.parse_stmt()?);
+                    self.next();
+                }
+            }
+        }
+
+        Ok(stmts)
     }
 
-    fn parse_stmt(&mut self) -> Box<dyn Stmt> {        
-        self.parse_expr_stmt()
+    /// Parses `import "path" as name`, starting at `import`.
+    fn parse_import(&mut self) -> error::Result<Box<dyn Stmt>> {
+        self.next();
+
+        let TokenKind::String(path) = self.current().kind() else {
+            return self.error("Expected a path in quotes after 'import'")
+        };
+        let path = path.clone();
+
+        self.next();
+
+        if self.current().kind() != &TokenKind::Ident("as".to_owned()) {
+            return self.error(format!("Expected 'as' after \"{path}\" in import"))
+        }
+
+        self.next();
+
+        let alias = match self.current().kind() {
+            TokenKind::Ident(alias) if !Self::KEYWORDS.contains(&alias.as_str()) => alias.clone(),
+            _ => return self.error("Expected a name after 'as' in import")
+        };
+
+        if self.match_next(&[&TokenKind::EOL, &TokenKind::Semicolon]) || self.peek_kind() == Some(&TokenKind::EOF) {
+            Ok(Box::new(Import(path, alias)))
+        } else {
+            self.error("Expected ';' or EOL after import")
+        }
     }
 
-    fn parse_expr_stmt(&mut self) -> Box<dyn Stmt> {
-        let expr = self.parse_expr(false);
+    fn parse_expr_stmt(&mut self) -> error::Result<Box<dyn Stmt>> {
+        let expr = self.parse_expr(false)?;
 
         let mut log_endl = None;
 
@@ -59,54 +127,84 @@ impl<'t> Parser<'t> {
             log_endl = Some(false);
         }
 
-        if log_endl.is_some() {
-            Box::new(ExprStmt(expr, log_endl.unwrap()))
+        // The last statement of a block can end at its `end`, as in `do 1 end`, and isn't logged, since it is the block's value
+        if log_endl.is_none() && self.peek_kind() == Some(&TokenKind::Ident("end".to_owned())) {
+            log_endl = Some(false);
+        }
+
+        if let Some(log_endl) = log_endl {
+            Ok(Box::new(ExprStmt(expr, log_endl)))
         } else {
-            panic!("Expected ';' or EOL.")
+            // The error is at whatever follows the expression
+            self.next();
+            self.error("Expected ';' or EOL")
         }
     }
 
-    fn validate_args(&self, args: &[Box<dyn Expr>]) -> Vec<Symbol> {
-        args
-            .into_iter()
+    /// Checks that each argument in a function definition is a distinct name, perhaps typed inline like `x : Nat`, and splits them into their names and types.
+    fn validate_args(&self, args: &[Box<dyn Expr>]) -> error::Result<(Vec<Symbol>, ArgTypes)> {
+        let (names, types): (Vec<Symbol>, ArgTypes) = args
+            .iter()
             .map(|a| {
                 if let Some(Symbol(arg)) = a.downcast_ref() {
-                    return Symbol(arg.clone());
+                    return Ok((Symbol(arg.clone()), None));
+                } else if let Some(TypeExpr(arg, typeset)) = a.downcast_ref() {
+                    if let Some(Symbol(arg)) = arg.downcast_ref() {
+                        return Ok((Symbol(arg.clone()), Some(typeset.to_owned())));
+                    }
                 }
 
-                panic!("Invalid left-hand for function definition: f args");
+                self.error(format!("Invalid argument '{a}' in function definition, expected a name"))
             })
-            .collect()
+            .collect::<error::Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+
+        for (i, Symbol(name)) in names.iter().enumerate() {
+            if names[..i].iter().any(|Symbol(earlier)| earlier == name) {
+                // The body is already parsed, so the repeated name is the last one before the definition's '='
+                let token = self.tokens[..self.i]
+                    .iter()
+                    .rev()
+                    .skip_while(|token| token.kind() != &TokenKind::Eq)
+                    .find(|token| token.kind() == &TokenKind::Ident(name.to_owned()))
+                    .unwrap_or(self.current());
+
+                return Err(Error::Parser(ParseError::DuplicateParameter { name: name.to_owned(), line: token.line(), col: token.col() }));
+            }
+        }
+
+        Ok((names, types))
     }
 
     /// Parses the current expression.
     /// 
     /// `can_span_lines` determines whether or not the expresson can span multiple lines, as in:
-    /// ```
+    /// ```text
     /// 1
     /// - 2
     /// ```
     /// Would usually output `1` and then `-2`.
     /// However, in a grouping, it is different:
-    /// ```
+    /// ```text
     /// (
     /// 1
     /// - 2
     /// )
     /// ``` 
     /// Here, it is known that the whole thing is one expression, so it won't think each EOL is ending the statement. It would output `-1`.
-    fn parse_expr(&mut self, can_span_lines: bool) -> Box<dyn Expr> {
+    fn parse_expr(&mut self, can_span_lines: bool) -> error::Result<Box<dyn Expr>> {
         self.parse_assign(can_span_lines)
     }
 
-    fn parse_assign(&mut self, can_span_lines: bool) -> Box<dyn Expr> {
-        let expr = self.parse_type(can_span_lines);
+    fn parse_assign(&mut self, can_span_lines: bool) -> error::Result<Box<dyn Expr>> {
+        let expr = self.parse_type(can_span_lines)?;
 
         if self.match_next(&[&TokenKind::Eq]) {
             self.skip_eol();
             self.next();
             
-            let right = self.parse_assign(can_span_lines);
+            let right = self.parse_assign(can_span_lines)?;
 
             // Parse func: f(x, y, ...) = expr
             if let Some(Call(left, args)) = expr.downcast_ref() {
@@ -117,58 +215,86 @@ impl<'t> Parser<'t> {
                 };
 
                 if name.is_none() {
-                    panic!("Invalid left-hand for function definition: f name");
+                    return self.error(format!("Invalid function name '{left}' in function definition"));
                 }
                 
                 if args.iter().any(Option::is_none) {
-                    panic!("Function notation requires every argument be defined")
+                    return self.error("Function notation requires every argument be defined");
                 }
 
-                let args = self.validate_args(&args
+                let (args, arg_types) = self.validate_args(&args
                     .iter()
                     .map(|a| a.to_owned().unwrap())
-                    .collect::<Vec<_>>());
+                    .collect::<Vec<_>>())?;
                 
-                return Box::new(Assign(Symbol(name.unwrap().clone()), Box::new(Func(args, right))))
+                return Ok(Box::new(Assign(Symbol(name.unwrap().clone()), Box::new(Func(args, right, arg_types)))));
             // Parse var: x = expr
             } else if let Some(Symbol(name)) = expr.downcast_ref() {
-                return Box::new(Assign(Symbol(name.to_owned()), right));
+                return Ok(Box::new(Assign(Symbol(name.to_owned()), right)));
             } else if let Some(TypeExpr(sym, typeset)) = expr.downcast_ref::<TypeExpr>() {
                 if let Some(Symbol(name)) = sym.downcast_ref() {
-                    return Box::new(TypedAssign(Symbol(name.to_owned()), typeset.to_owned(), right))
+                    return Ok(Box::new(TypedAssign(Symbol(name.to_owned()), typeset.to_owned(), right)));
                 }
             }
 
-            panic!("Invalid left-hand for assignment"); // TODO: Have actual error reporting
+            return self.error(format!("Invalid left-hand '{expr}' for assignment"));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn parse_type(&mut self, can_span_lines: bool) -> Box<dyn Expr> {
-        let expr = self.parse_or(can_span_lines);
+    fn parse_type(&mut self, can_span_lines: bool) -> error::Result<Box<dyn Expr>> {
+        let expr = self.parse_or(can_span_lines)?;
 
         if self.match_next(&[&TokenKind::Colon]) {
+            let (line, col) = (self.current().line(), self.current().col());
             self.skip_eol();
+
+            // a missing type is reported at the ':' itself, rather than at whatever line the search for one stopped on
+            if let Some(TokenKind::EOL | TokenKind::EOF | TokenKind::Semicolon) = self.peek_kind() {
+                return Err(Error::Parser(ParseError::ExpectedType { line, col }));
+            }
+
             self.next();
 
-            let right = self.parse_or(can_span_lines);
+            let right = self.parse_or(can_span_lines)?;
+
+            // A comma only lists more domain types if an arrow follows them, as in `f : Int, Str -> Bool`; otherwise it belongs to whatever
+            // surrounds this, like the other arguments in `f(x : Int, y) = x`
+            let start = self.i;
+            let mut domain = vec![];
+
+            while self.match_next(&[&TokenKind::Comma]) {
+                if self.peek_kind() == Some(&TokenKind::SmallArrow) {
+                    return self.error("Expected a type after ',', but got '->'");
+                }
+
+                self.skip_eol();
+                self.next();
+
+                match self.parse_or(can_span_lines) {
+                    Ok(typeset) => domain.push(typeset),
+                    Err(_) => break
+                }
+            }
 
             if self.match_next(&[&TokenKind::SmallArrow]) {
                 self.next();
-                let codomain = self.parse_or(can_span_lines);
+                let codomain = self.parse_or(can_span_lines)?;
 
-                return Box::new(FuncTypeExpr(expr, vec![right], codomain))
+                domain.insert(0, right);
+                return Ok(Box::new(FuncTypeExpr(expr, domain, codomain)));
             } else {
-                return Box::new(TypeExpr(expr, right)) // Could be a cast (x : Int AFTER x is defined) or a type-declaration (x : Int BEFORE x is defined)
+                self.i = start;
+                return Ok(Box::new(TypeExpr(expr, right))); // Could be a cast (x : Int AFTER x is defined) or a type-declaration (x : Int BEFORE x is defined)
             }
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn parse_or(&mut self, can_span_lines: bool) -> Box<dyn Expr> {
-        let mut expr = self.parse_and(can_span_lines);
+    fn parse_or(&mut self, can_span_lines: bool) -> error::Result<Box<dyn Expr>> {
+        let mut expr = self.parse_and(can_span_lines)?;
 
         while self.match_next(&[&TokenKind::DblBar]) {
             let op = self.current().clone();
@@ -176,16 +302,16 @@ impl<'t> Parser<'t> {
             self.skip_eol();
             self.next();
 
-            let right = self.parse_and(can_span_lines);
+            let right = self.parse_and(can_span_lines)?;
 
             expr = Box::new(Binary(expr, op, right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn parse_and(&mut self, can_span_lines: bool) -> Box<dyn Expr> {
-        let mut expr = self.parse_comp(can_span_lines);
+    fn parse_and(&mut self, can_span_lines: bool) -> error::Result<Box<dyn Expr>> {
+        let mut expr = self.parse_comp(can_span_lines)?;
 
         while self.match_next(&[&TokenKind::DblAmp]) {
             let op = self.current().clone();
@@ -193,21 +319,22 @@ impl<'t> Parser<'t> {
             self.skip_eol();
             self.next();
 
-            let right = self.parse_comp(can_span_lines);
+            let right = self.parse_comp(can_span_lines)?;
 
             expr = Box::new(Binary(expr, op, right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    // TODO: Have it allow for a < b < c.
-    // Perhaps in another pass? As it will have to check if the type implements the Ord class rather than just PartialOrd.
-    fn parse_comp(&mut self, can_span_lines: bool) -> Box<dyn Expr> {
-        let mut expr = self.parse_set_comp(can_span_lines);
+    /// Parses a comparison, or a chain of them. `a < b <= c` is parsed as a [`Chain`], which means `a < b && b <= c`, but only executes `b` once.
+    // Perhaps this will have to check if the type implements the Ord class rather than just PartialOrd.
+    fn parse_comp(&mut self, can_span_lines: bool) -> error::Result<Box<dyn Expr>> {
+        let first = self.parse_set_comp(can_span_lines)?;
+        let mut links = vec![];
 
         while self.match_next(&[
-            &TokenKind::DblEq, 
+            &TokenKind::DblEq, &TokenKind::BangEq,
             &TokenKind::Less, &TokenKind::Greater,
             &TokenKind::LessEq, &TokenKind::GreaterEq
         ]) {
@@ -216,36 +343,43 @@ impl<'t> Parser<'t> {
             self.skip_eol();
             self.next();
 
-            let right = self.parse_set_comp(can_span_lines);
-
-            expr = Box::new(Binary(expr, op, right));
+            links.push((op, self.parse_set_comp(can_span_lines)?));
         }
 
-        expr
+        Ok(match links.len() {
+            0 => first,
+            1 => {
+                let (op, right) = links.pop().unwrap();
+
+                Box::new(Binary(first, op, right))
+            }
+            _ => Box::new(Chain(first, links))
+        })
     }
 
-    fn parse_set_comp(&mut self, can_span_lines: bool) -> Box<dyn Expr> {
-        let mut expr = self.parse_set_ops(can_span_lines);
+    fn parse_set_comp(&mut self, can_span_lines: bool) -> error::Result<Box<dyn Expr>> {
+        let mut expr = self.parse_set_ops(can_span_lines)?;
 
         while self.match_next(&[
             &TokenKind::EqColon,
             &TokenKind::LessColon, &TokenKind::GreaterColon,
+            &TokenKind::LessEqColon, &TokenKind::GreaterEqColon,
         ]) {
             let op = self.current().clone();
 
             self.skip_eol();
             self.next();
 
-            let right = self.parse_set_ops(can_span_lines);
+            let right = self.parse_set_ops(can_span_lines)?;
 
             expr = Box::new(Binary(expr, op, right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn parse_set_ops(&mut self, can_span_lines: bool) -> Box<dyn Expr> {
-        let mut expr = self.parse_term(can_span_lines);
+    fn parse_set_ops(&mut self, can_span_lines: bool) -> error::Result<Box<dyn Expr>> {
+        let mut expr = self.parse_term(can_span_lines)?;
 
         while self.match_next(&[&TokenKind::Amp, &TokenKind::Bar, &TokenKind::BackSlash, &TokenKind::Tilde]) {
             let op = self.current().clone();
@@ -253,16 +387,16 @@ impl<'t> Parser<'t> {
             self.skip_eol();
             self.next();
 
-            let right = self.parse_term(can_span_lines);
+            let right = self.parse_term(can_span_lines)?;
 
             expr = Box::new(Binary(expr, op, right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn parse_term(&mut self, can_span_lines: bool) -> Box<dyn Expr> {
-        let mut expr = self.parse_factor(can_span_lines);
+    fn parse_term(&mut self, can_span_lines: bool) -> error::Result<Box<dyn Expr>> {
+        let mut expr = self.parse_factor(can_span_lines)?;
 
         while self.match_next(&[&TokenKind::Plus, &TokenKind::Minus]) {
             let op = self.current().clone();
@@ -270,51 +404,69 @@ impl<'t> Parser<'t> {
             self.skip_eol();
             self.next();
 
-            let right = self.parse_factor(can_span_lines);
+            let right = self.parse_factor(can_span_lines)?;
 
             expr = Box::new(Binary(expr, op, right));
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn parse_factor(&mut self, can_span_lines: bool) -> Box<dyn Expr> {
-        let mut expr = self.parse_unary(can_span_lines);
+    fn parse_factor(&mut self, can_span_lines: bool) -> error::Result<Box<dyn Expr>> {
+        let mut expr = self.parse_unary(can_span_lines)?;
 
-        while self.match_next(&[&TokenKind::Slash, &TokenKind::Star]) {
-            let op = self.current().clone();
+        loop {
+            let op = if self.match_next(&[&TokenKind::Slash, &TokenKind::Star]) {
+                let op = self.current().clone();
+
+                self.skip_eol();
+                op
+            } else if self.is_implicit_product() {
+                Token::new(TokenKind::Star, self.current().line(), self.current().col())
+            } else {
+                break
+            };
 
-            self.skip_eol();
             self.next();
 
-            let right = self.parse_unary(can_span_lines);
+            let right = self.parse_unary(can_span_lines)?;
 
             expr = Box::new(Binary(expr, op, right));
         }
 
-        expr
+        Ok(expr)
+    }
+
+    /// Whether a number is directly followed by a name, like `2x` or `2 e`, which multiplies them.
+    fn is_implicit_product(&self) -> bool {
+        matches!(self.current().kind(), TokenKind::Number(_)) && matches!(
+            self.peek_kind(),
+            Some(TokenKind::Ident(lexeme)) if !Self::KEYWORDS.contains(&lexeme.as_str())
+        )
     }
 
-    fn parse_unary(&mut self, can_span_lines: bool) -> Box<dyn Expr> {
+    fn parse_unary(&mut self, can_span_lines: bool) -> error::Result<Box<dyn Expr>> {
         match self.current().kind() {
             TokenKind::Bang  |
             TokenKind::Minus |
-            TokenKind::Plus  => {
+            TokenKind::Plus  |
+            TokenKind::Tilde |
+            TokenKind::Hash  => {
                 let op = self.current().clone();
 
                 self.skip_eol();
                 self.next();
 
-                let right = self.parse_unary(can_span_lines);
+                let right = self.parse_unary(can_span_lines)?;
 
-                return Box::new(Unary(op, right));
+                Ok(Box::new(Unary(op, right)))
             }
             _ => self.parse_power(can_span_lines)
         }
     }
 
-    fn parse_power(&mut self, can_span_lines: bool) -> Box<dyn Expr> {
-        let mut expr = self.parse_call();
+    fn parse_power(&mut self, can_span_lines: bool) -> error::Result<Box<dyn Expr>> {
+        let mut expr = self.parse_compose()?;
 
         if can_span_lines {
             self.skip_eol();
@@ -326,25 +478,126 @@ impl<'t> Parser<'t> {
             self.skip_eol();
             self.next();
 
-            let right = self.parse_unary(can_span_lines);
+            let right = self.parse_unary(can_span_lines)?;
+            expr = Box::new(Binary(expr, op, right));
+        }
+
+        Ok(expr)
+    }
+
+    /// Parses function composition (eg. f . g), which binds tighter than everything but calls and postfix operators.
+    fn parse_compose(&mut self) -> error::Result<Box<dyn Expr>> {
+        let mut expr = self.parse_postfix()?;
+
+        while self.match_next(&[&TokenKind::Dot]) {
+            let op = self.current().clone();
+
+            self.skip_eol();
+            self.next();
+
+            let right = self.parse_postfix()?;
+
             expr = Box::new(Binary(expr, op, right));
         }
 
-        expr 
+        Ok(expr)
+    }
+
+    fn parse_postfix(&mut self) -> error::Result<Box<dyn Expr>> {
+        let mut expr = self.parse_call()?;
+
+        // Factorial (eg. 5!, 3!!)
+        while self.match_next(&[&TokenKind::Bang]) {
+            expr = Box::new(Postfix(expr, self.current().clone()));
+        }
+
+        Ok(expr)
     }
 
-    fn parse_call(&mut self) -> Box<dyn Expr> {
-        let mut expr = self.parse_primary();
+    fn parse_call(&mut self) -> error::Result<Box<dyn Expr>> {
+        let mut expr = self.parse_primary()?;
+
+        // `name.member`, written without spaces, reaches into an imported module, so it binds tighter than `f . g` and calls
+        while expr.downcast_ref::<Symbol>().is_some() || is_member(expr.as_ref()) {
+            let Some(member) = self.adjacent_member() else {
+                break
+            };
+
+            self.next();
+            let op = self.current().clone();
+            self.next();
+
+            expr = Box::new(Binary(expr, op, Box::new(Symbol(member))));
+        }
 
         if self.match_next(&[&TokenKind::OpenParen]) {
             self.skip_eol();
-            expr = self.finish_call(expr);
+            expr = self.finish_call(expr)?;
+        }
+
+        // `t[i]` and `t.0` are elements of a tuple or string. A number already took any decimal point after it, so this is never `2.5`
+        loop {
+            if self.match_next(&[&TokenKind::OpenBracket]) {
+                self.skip_eol();
+                self.next();
+
+                let index = self.parse_expr(true)?;
+
+                self.skip_eol();
+
+                // `t[a..b]` is a slice
+                if self.match_next(&[&TokenKind::DblDot]) {
+                    self.skip_eol();
+                    self.next();
+
+                    let end = self.parse_expr(true)?;
+
+                    self.skip_eol();
+
+                    if !self.match_next(&[&TokenKind::CloseBracket]) {
+                        return self.error("Expected ']' after slice");
+                    }
+
+                    expr = Box::new(Slice(expr, index, end));
+                    continue;
+                }
+
+                if !self.match_next(&[&TokenKind::CloseBracket]) {
+                    return self.error("Expected ']' after index");
+                }
+
+                expr = Box::new(Index(expr, index));
+            } else if let (Some(TokenKind::Dot), Some(TokenKind::Number(digits))) = (self.peek_kind(), self.tokens.get(self.i + 2).map(Token::kind)) {
+                let Ok(i) = digits.replace('_', "").parse::<BigInt>() else {
+                    break
+                };
+
+                self.next();
+                self.next();
+
+                expr = Box::new(Index(expr, Box::new(Literal(Box::new(i)))));
+            } else {
+                break
+            }
         }
 
-        expr
+        Ok(expr)
     }
 
-    fn finish_call(&mut self, callee: Box<dyn Expr>) -> Box<dyn Expr> {
+    /// Returns the name in a `.name` right after the current identifier, if there are no spaces between them.
+    fn adjacent_member(&self) -> Option<String> {
+        let (ident, dot, after) = (self.current(), self.tokens.get(self.i + 1)?, self.tokens.get(self.i + 2)?);
+
+        match (ident.kind(), dot.kind(), after.kind()) {
+            (TokenKind::Ident(name), TokenKind::Dot, TokenKind::Ident(member))
+                if ident.line() == dot.line() && dot.line() == after.line()
+                && ident.col() + name.chars().count() == dot.col() && dot.col() + 1 == after.col()
+                && !Self::KEYWORDS.contains(&member.as_str()) => Some(member.clone()),
+            _ => None
+        }
+    }
+
+    fn finish_call(&mut self, callee: Box<dyn Expr>) -> error::Result<Box<dyn Expr>> {
         let mut args = vec![];
 
         // A do-while loop
@@ -359,7 +612,7 @@ impl<'t> Parser<'t> {
                 self.skip_eol();
                 self.next();
 
-                args.push(Some(self.parse_expr(true)));
+                args.push(Some(self.parse_expr(true)?));
 
                 self.skip_eol();
 
@@ -371,21 +624,20 @@ impl<'t> Parser<'t> {
             }
         } {}
 
-        if self.match_next(&[&TokenKind::CloseParen]) {
-            ()
-        } else {
-            panic!("Expected ')' after arguments");
+        if !self.match_next(&[&TokenKind::CloseParen]) {
+            return self.error("Expected ')' after arguments");
         }
 
         if self.match_next(&[&TokenKind::OpenParen]) {
             self.finish_call(Box::new(Call(callee, args)))
         } else {
-            Box::new(Call(callee, args))
+            Ok(Box::new(Call(callee, args)))
         }
     }
 
-    fn parse_primary(&mut self) -> Box<dyn Expr> {
-        return match self.current().kind() {
+    fn parse_primary(&mut self) -> error::Result<Box<dyn Expr>> {
+        match self.current().kind() {
+            TokenKind::Ident(lexeme) if lexeme == "do" => Ok(Box::new(BlockExpr(self.parse_block_stmts()?))),
             TokenKind::Ident(lexeme) => self.parse_ident(lexeme.clone()),
             TokenKind::String(lexeme) => self.parse_string(lexeme.clone()),
             TokenKind::Char(lexeme) => self.parse_char(lexeme.clone()),
@@ -394,12 +646,13 @@ impl<'t> Parser<'t> {
             TokenKind::OpenBracket => self.parse_list(),
             TokenKind::OpenBrace => self.parse_set(),
             
-            _ => panic!("Expected expression {:#?}", &self.tokens[self.i..]) // Todo: change for actual error handling
+            TokenKind::EOL | TokenKind::EOF => self.error("Expected an expression, but the line ended"),
+            _ => self.error(format!("Expected an expression, but got '{}'", self.current().lexeme()))
         }
     }
 
-    fn parse_ident(&mut self, lexeme: String) -> Box<dyn Expr> {
-        if &lexeme == "i" {
+    fn parse_ident(&mut self, lexeme: String) -> error::Result<Box<dyn Expr>> {
+        Ok(if &lexeme == "i" {
             Box::new(Literal(
                 Box::new(
                     Complex::<BigRational>::new(
@@ -412,59 +665,75 @@ impl<'t> Parser<'t> {
             Box::new(Literal(Box::new(true)))
         } else if lexeme == "false" {
             Box::new(Literal(Box::new(false)))
-        } else if let Some(&_keyword) = Self::KEYWORDS.iter().find(|&k| k == &lexeme) {
-            todo!()
+        } else if lexeme == "unit" {
+            Box::new(Literal(Box::new(Unit)))
+        } else if let Some(&keyword) = Self::KEYWORDS.iter().find(|&k| k == &lexeme) {
+            return self.error(format!("Keyword '{keyword}' is not supported yet"))
             // Box::new(Keyword(keyword.to_owned()))
         } else {
             Box::new(Symbol(lexeme))
-        }
+        })
     }
 
-    fn parse_string(&mut self, lexeme: String) -> Box<dyn Expr> {
-        Box::new(Literal(Box::new(lexeme)))
+    fn parse_string(&mut self, lexeme: String) -> error::Result<Box<dyn Expr>> {
+        Ok(Box::new(Literal(Box::new(lexeme))))
     }
 
-    fn parse_char(&mut self, lexeme: String) -> Box<dyn Expr> {
-        Box::new(Literal(Box::new(lexeme))) // todo, make `struct Char(String)` struct to hold chars
+    fn parse_char(&mut self, lexeme: String) -> error::Result<Box<dyn Expr>> {
+        Ok(Box::new(Literal(Box::new(lexeme)))) // todo, make `struct Char(String)` struct to hold chars
     }
 
-    fn parse_number(&mut self, mut l1: String) -> Box<dyn Expr> {
-        // Decimal (eg. 12.34)
-        let mut num = if self.match_next(&[&TokenKind::Dot]) {
-            fn getlen(s: &str) -> BigInt {
-                let mut n = BigInt::from(1);
+    fn parse_number(&mut self, l1: String) -> error::Result<Box<dyn Expr>> {
+        fn getlen(s: &str) -> BigInt {
+            let mut n = BigInt::from(1);
 
-                for _ in s.chars() {
-                    n *= 10;
-                }
-
-                n
+            for _ in s.chars() {
+                n *= 10;
             }
 
+            n
+        }
+
+        let (mut digits, mut exponent) = self.split_exponent(&l1)?;
+        let mut denom = None;
+
+        // Decimal (eg. 12.34), unless the exponent already ended the number (eg. 1e3.4)
+        if exponent.is_none() && self.match_next(&[&TokenKind::Dot]) {
             if let Some(t) = self.peek() {
                 if let TokenKind::Number(l2) = t.kind() {
-                    let l2 = l2.clone();
-                    l1.push_str(&l2);
+                    let (l2, l2_exponent) = self.split_exponent(l2)?;
+
+                    digits.push_str(&l2);
+                    denom = Some(getlen(&l2));
+                    exponent = l2_exponent;
+
                     self.next();
+                }
+            }
+        }
+
+        let numer = digits.parse::<BigInt>().unwrap();
+        let exponent = exponent.unwrap_or(0);
+        let scale = BigInt::from(10).pow(exponent.unsigned_abs());
+
+        let mut num = match denom {
+            // Int (eg. 1234, 12e3)
+            None if exponent >= 0 => Box::new(Literal(Box::new(numer * scale))),
 
-                    Box::new(Literal(Box::new(BigRational::new(l1.parse().unwrap(), getlen(&l2)))))
+            // Real (eg. 12.34, 1.5e-2, 12e-3)
+            denom => {
+                let denom = denom.unwrap_or_else(BigInt::one);
+
+                Box::new(Literal(Box::new(if exponent >= 0 {
+                    BigRational::new(numer * scale, denom)
                 } else {
-                    Box::new(Literal(Box::new(l1.parse::<BigInt>().unwrap())))
-                }
-            } else {
-                Box::new(Literal(Box::new(l1.parse::<BigInt>().unwrap())))
+                    BigRational::new(numer, denom * scale)
+                })))
             }
-        // Int (eg. 1234)
-        } else {
-            Box::new(Literal(Box::new(l1.parse::<BigInt>().unwrap())))
         };
 
-
         if self.match_next(&[&TokenKind::Ident("i".to_owned())]) {
-            let val = match *num {
-                Literal(v) => v,
-                _ => unreachable!()
-            };
+            let Literal(val) = *num;
             
             let new_val = if let Some(bigint) = val.as_any().downcast_ref::<BigInt>() {
                 Complex::<BigRational>::new(BigRational::zero(), BigRational::from(bigint.to_owned()))
@@ -477,27 +746,51 @@ impl<'t> Parser<'t> {
             num = Box::new(Literal(Box::new(new_val)));
         }
 
-        num
+        Ok(num)
+    }
+
+    /// Splits a number lexeme like `15e-2` into its digits (`15`) and its exponent (`-2`), if it has one. Digit separators (`1_000`) are removed.
+    fn split_exponent(&self, lexeme: &str) -> error::Result<(String, Option<i64>)> {
+        let stripped = lexeme.replace('_', "");
+
+        if let Some((digits, exponent)) = stripped.split_once(['e', 'E']) {
+            let Ok(exponent) = exponent.parse::<i64>() else {
+                return self.error(format!("Invalid exponent in number '{lexeme}'"))
+            };
+
+            // The number is computed exactly, so a huge exponent would take forever
+            if exponent.unsigned_abs() > Self::MAX_EXPONENT {
+                return self.error(format!("Exponent in number '{lexeme}' is larger than {}", Self::MAX_EXPONENT))
+            }
+
+            Ok((digits.to_owned(), Some(exponent)))
+        } else {
+            Ok((stripped, None))
+        }
     }
 
-    fn parse_grouping(&mut self) -> Box<dyn Expr> {
+    fn parse_grouping(&mut self) -> error::Result<Box<dyn Expr>> {
         self.skip_eol();
+
+        // Empty parentheses are the unit value
+        if self.match_next(&[&TokenKind::CloseParen]) {
+            return Ok(Box::new(Literal(Box::new(Unit))));
+        }
+
         self.next();
 
-        let expr = self.parse_expr(true);
+        let expr = self.parse_expr(true)?;
 
         self.skip_eol();
         
-        if self.match_next(&[&TokenKind::CloseParen]) {
-            ()
-        } else {
-            panic!("Closing parenthesis expected");
+        if !self.match_next(&[&TokenKind::CloseParen]) {
+            return self.error("Closing parenthesis expected");
         }
 
-        Box::new(Group(expr))
+        Ok(Box::new(Group(expr)))
     }
 
-    fn parse_list(&mut self) -> Box<dyn Expr> {
+    fn parse_list(&mut self) -> error::Result<Box<dyn Expr>> {
         self.skip_eol();
         self.next();
 
@@ -506,7 +799,7 @@ impl<'t> Parser<'t> {
         let mut list = Vec::new();
 
         while self.current().kind() != &TokenKind::CloseBracket {
-            list.push(self.parse_expr(true));
+            list.push(self.parse_expr(true)?);
 
             self.skip_eol();
 
@@ -517,11 +810,11 @@ impl<'t> Parser<'t> {
                 continue
             // If sees semicolon, it creates matrix instead of list (tuple)
             } else if self.match_next(&[&TokenKind::Semicolon]) {
-                if let None = matrix_dim {
+                if matrix_dim.is_none() {
                     matrix_dim = Some((1usize, list.len()));
                 } else if let Some((r, c)) = matrix_dim {
                     if c != list.len() {
-                        panic!("Each row of a matrix must have the same length: 1");
+                        return self.error("Each row of a matrix must have the same length");
                     }
 
                     matrix_dim = Some((r + 1, c));
@@ -536,7 +829,7 @@ impl<'t> Parser<'t> {
             } else if self.match_next(&[&TokenKind::CloseBracket]) {
                 if let Some((r, c)) = matrix_dim {
                     if c != list.len() {
-                        panic!("Each row of a matrix must have the same length: 2");
+                        return self.error("Each row of a matrix must have the same length");
                     }
                     
                     matrix_dim = Some((r + 1, c));
@@ -551,27 +844,40 @@ impl<'t> Parser<'t> {
 
                 continue;
             } else if self.match_next(&[&TokenKind::EOF]) {
-                panic!("Expected ']'");
+                return self.error("Expected ']'");
             } else {
-                panic!("Expected ',', ';', or ']'");
+                return self.error("Expected ',', ';', or ']'");
             }
         }
 
-        if let Some(_) = matrix_dim {
-            Box::new(Matrix(result))
+        if matrix_dim.is_some() {
+            Ok(Box::new(Matrix(result)))
         } else {
-            Box::new(Tuple(list))
+            Ok(Box::new(Tuple(list)))
         }
     }
 
-    fn parse_set(&mut self) -> Box<dyn Expr> {
+    fn parse_set(&mut self) -> error::Result<Box<dyn Expr>> {
         self.skip_eol();
         self.next();
 
+        // Newlines don't end anything inside the braces, so they are skipped over when looking ahead
+        let after = self.tokens.iter().skip(self.i + 1).map(Token::kind).find(|kind| kind != &&TokenKind::EOL);
+
+        if let (TokenKind::Ident(name), Some(TokenKind::Colon)) = (self.current().kind(), after) {
+            let name = name.to_owned();
+
+            return self.parse_set_builder(name)
+        }
+
+        if self.is_comprehension() {
+            return self.parse_comprehension()
+        }
+
         let mut values = Vec::new();
 
         while self.current().kind() != &TokenKind::CloseBrace {
-            values.push(self.parse_expr(true));
+            values.push(self.parse_expr(true)?);
 
             self.skip_eol();
 
@@ -581,7 +887,7 @@ impl<'t> Parser<'t> {
 
                 continue
             } else if self.match_next(&[&TokenKind::Semicolon]) {
-                panic!("Elements in a set must be separated by ','s not ';'s")
+                return self.error("Elements in a set must be separated by ','s not ';'s")
             } else if self.match_next(&[&TokenKind::CloseBrace]) {
                 break
             } else if self.match_next(&[&TokenKind::EOL]) {
@@ -590,20 +896,143 @@ impl<'t> Parser<'t> {
 
                 continue;
             } else if self.match_next(&[&TokenKind::EOF]) {
-                panic!("Expected '}}'");
+                return self.error("Expected '}'");
             } else {
-                panic!("Expected ',' or '}}'");
+                return self.error("Expected ',' or '}'");
+            }
+        }
+
+        Ok(Box::new(Set(values)))
+    }
+
+    /// Parses the rest of `{ x : x <: S & cond }`, after the bound variable `x`. The condition is optional.
+    /// 
+    /// The source set is parsed before any set operators, so a `&` after it starts the condition, and `{ x : x <: (A | B) }` needs parentheses.
+    fn parse_set_builder(&mut self, name: String) -> error::Result<Box<dyn Expr>> {
+        self.skip_eol();
+        self.next(); // :
+        self.skip_eol();
+        self.next();
+
+        match self.current().kind() {
+            TokenKind::Ident(bound) if bound == &name => (),
+            _ => return self.error(format!("Expected '{name}' after ':' in set builder"))
+        }
+
+        if !self.match_next(&[&TokenKind::LessColon]) {
+            return self.error(format!("Expected '<:' after '{name}' in set builder"));
+        }
+
+        self.skip_eol();
+        self.next();
+
+        let source = self.parse_unary(true)?;
+        let cond = self.finish_set_builder()?;
+
+        Ok(Box::new(SetBuilder(Symbol(name), source, cond)))
+    }
+
+    /// Checks whether the set starting at the current token is written `{ output | x : A, y : B }`, by looking for a `|` followed by `name :` outside of any brackets.
+    /// Newlines are skipped over, as they may be anywhere inside the braces.
+    fn is_comprehension(&self) -> bool {
+        let mut depth = 0usize;
+        let kinds = self.tokens
+            .iter()
+            .skip(self.i)
+            .map(Token::kind)
+            .filter(|kind| kind != &&TokenKind::EOL)
+            .collect::<Vec<_>>();
+
+        for (i, kind) in kinds.iter().enumerate() {
+            match kind {
+                TokenKind::OpenParen | TokenKind::OpenBracket | TokenKind::OpenBrace => depth += 1,
+                TokenKind::CloseParen | TokenKind::CloseBracket => depth = depth.saturating_sub(1),
+                TokenKind::CloseBrace if depth == 0 => return false,
+                TokenKind::CloseBrace => depth -= 1,
+                TokenKind::Bar if depth == 0 => if let (Some(TokenKind::Ident(_)), Some(TokenKind::Colon)) = (
+                    kinds.get(i + 1),
+                    kinds.get(i + 2)
+                ) {
+                    return true
+                },
+                TokenKind::EOF => return false,
+                _ => ()
+            }
+        }
+
+        false
+    }
+
+    /// Parses `{ output | x : A, y : B & cond }`, starting at the output. The condition is optional.
+    /// 
+    /// The output is parsed before any set operators or comparisons, so its `|` ends it, and `{ (x < y) | x : A, y : B }` needs parentheses.
+    fn parse_comprehension(&mut self) -> error::Result<Box<dyn Expr>> {
+        let output = self.parse_term(true)?;
+        self.skip_eol();
+
+        if !self.match_next(&[&TokenKind::Bar]) {
+            return self.error("Expected '|' after the output of a set builder");
+        }
+
+        let mut binders = Vec::new();
+
+        loop {
+            self.skip_eol();
+            self.next();
+
+            let name = match self.current().kind() {
+                TokenKind::Ident(name) => name.to_owned(),
+                _ => return self.error(format!("Expected a variable in set builder, but got '{}'", self.current().lexeme()))
+            };
+
+            self.skip_eol();
+
+            if !self.match_next(&[&TokenKind::Colon]) {
+                return self.error(format!("Expected ':' after '{name}' in set builder"));
+            }
+
+            self.skip_eol();
+            self.next();
+
+            binders.push((Symbol(name), self.parse_unary(true)?));
+            self.skip_eol();
+
+            if !self.match_next(&[&TokenKind::Comma]) {
+                break
             }
         }
 
-        Box::new(Set(values))
+        let cond = self.finish_set_builder()?;
+
+        Ok(Box::new(Comprehension(output, binders, cond)))
+    }
+
+    /// Parses the optional `& cond` at the end of a set builder, and its closing `}`.
+    fn finish_set_builder(&mut self) -> error::Result<Option<Box<dyn Expr>>> {
+        self.skip_eol();
+
+        let cond = if self.match_next(&[&TokenKind::Amp, &TokenKind::DblAmp]) {
+            self.skip_eol();
+            self.next();
+
+            let cond = self.parse_expr(true)?;
+            self.skip_eol();
+
+            Some(cond)
+        } else {
+            None
+        };
+
+        if !self.match_next(&[&TokenKind::CloseBrace]) {
+            return self.error("Expected '}' after set builder");
+        }
+
+        Ok(cond)
     }
 
     /// Keeps skipping over tokens until the next token is not EOL.
     fn skip_eol(&mut self) {
-        while self.match_next(&[&TokenKind::EOL]) {
-            ()
-        }
+        while self.match_next(&[&TokenKind::EOL]) {}
     }
 
     /// Consumes next token if it matches the given [`TokenKind`]
@@ -644,7 +1073,135 @@ impl<'t> Parser<'t> {
         self.i += 1;
     }
 
+    /// Creates an error at the current token's line and column. The EOF token sits on the line after the last one, so errors there point at the token before it.
+    fn error<T>(&self, message: impl Into<String>) -> error::Result<T> {
+        let token = if self.current().kind() == &TokenKind::EOF && self.i > 0 {
+            &self.tokens[self.i - 1]
+        } else {
+            self.current()
+        };
+
+        Err(Error::Parser(ParseError::new(token.line(), token.col(), message)))
+    }
+
     fn current(&self) -> &Token {
         &self.tokens[self.i]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic;
+    use crate::lexer::Lexer;
+
+    fn parse_error(source: &str) -> Error {
+        let tokens = Lexer::new(source.as_bytes()).lex().unwrap();
+
+        match Parser::new(&tokens).parse() {
+            Ok(_) => panic!("expected '{source}' not to parse"),
+            Err(err) => err
+        }
+    }
+
+    fn parse(source: &str) -> Ast {
+        let tokens = Lexer::new(source.as_bytes()).lex().unwrap();
+
+        Parser::new(&tokens).parse().unwrap()
+    }
+
+    #[test]
+    fn block_is_an_expression() {
+        let ast = parse("x = do 1 end");
+        let Some(ExprStmt(expr, true)) = ast.stmts()[0].downcast_ref() else {
+            panic!("expected a logged expression statement")
+        };
+        let Some(Assign(_, value)) = expr.downcast_ref() else {
+            panic!("expected an assignment")
+        };
+
+        assert!(matches!(value.downcast_ref(), Some(BlockExpr(stmts)) if stmts.len() == 1));
+        assert_eq!(parse("y = 1 + do\n  2\nend * 3").stmts().len(), 1);
+        assert!(parse_error("x = do 1").to_string().contains("Expected 'end'"));
+    }
+
+    /// Returns the operator of a binary expression, and the operators of its operands, if they are binary expressions too.
+    fn grouping(source: &str) -> (TokenKind, Option<TokenKind>, Option<TokenKind>) {
+        let ast = parse(source);
+        let Some(ExprStmt(expr, _)) = ast.stmts()[0].downcast_ref() else {
+            panic!("expected '{source}' to be an expression statement")
+        };
+        let Some(Binary(left, op, right)) = expr.downcast_ref() else {
+            panic!("expected '{source}' to be a binary expression")
+        };
+        let op_of = |expr: &dyn Expr| expr.downcast_ref::<Binary>().map(|Binary(_, op, _)| op.kind().clone());
+
+        (op.kind().clone(), op_of(left.as_ref()), op_of(right.as_ref()))
+    }
+
+    #[test]
+    fn set_operators_share_a_level_and_group_from_the_left() {
+        assert_eq!(grouping("{1, 2, 3} \\ {2} | {4}"), (TokenKind::Bar, Some(TokenKind::BackSlash), None));
+        assert_eq!(grouping("A | B & C ~ D"), (TokenKind::Tilde, Some(TokenKind::Amp), None));
+        assert_eq!(grouping("A \\ (B | C)"), (TokenKind::BackSlash, None, None));
+
+        // Looser than addition, but tighter than comparisons
+        assert_eq!(grouping("A | B + C"), (TokenKind::Bar, None, Some(TokenKind::Plus)));
+        assert_eq!(grouping("A | B == C"), (TokenKind::DblEq, Some(TokenKind::Bar), None));
+    }
+
+    #[test]
+    fn huge_exponents_are_rejected() {
+        for source in ["1e999999999", "1.5e-100001", "2E100001i"] {
+            assert!(parse_error(source).to_string().contains("is larger than 100000"), "expected '{source}' to be rejected");
+        }
+
+        assert_eq!(parse("1e100000").stmts().len(), 1);
+    }
+
+    #[test]
+    fn parse_error_has_a_position() {
+        assert_eq!(parse_error("1 2").position(), Some((1, 3)));
+        assert_eq!(parse_error("x = 1\ny = 1 +").position(), Some((2, 8)));
+    }
+
+    #[test]
+    fn parse_error_caret_lands_on_its_column() {
+        let source = "x = 1\ny = 3 4";
+        let err = parse_error(source);
+        let (line, col) = err.position().unwrap();
+        let rendered = diagnostic::render(source, line, col, &err.to_string());
+
+        assert_eq!(rendered.lines().last(), Some("  |       ^"));
+        assert_eq!(rendered.lines().nth(3), Some("2 | y = 3 4"));
+    }
+
+    #[test]
+    fn colon_without_type_is_an_error() {
+        for source in ["x :", "x :\n", "y = 1\nx :;"] {
+            assert!(matches!(parse_error(source), Error::Parser(ParseError::ExpectedType { .. })));
+        }
+
+        assert_eq!(parse_error("y = 1\nx :").position(), Some((2, 3)));
+    }
+
+    #[test]
+    fn duplicate_parameter_is_an_error() {
+        let err = parse_error("f(x, x) = x");
+
+        assert!(matches!(&err, Error::Parser(ParseError::DuplicateParameter { name, .. }) if name == "x"));
+        assert_eq!(err.position(), Some((1, 6)));
+
+        let tokens = Lexer::new("f(x, y) = x".as_bytes()).lex().unwrap();
+        assert!(Parser::new(&tokens).parse().is_ok());
+    }
+
+    #[test]
+    fn newlines_are_skipped_inside_brackets() {
+        let one_line = parse("m = [1, 2; 3, 4]\ns = {1, 2}\nt = f(1, 2)\nx = (1 + 2)").to_string();
+        let spread = parse("m = [\n  1, 2;\n  3, 4\n]\ns = {\n  1,\n  2\n}\nt = f(1,\n  2)\nx = (1 +\n  2)").to_string();
+
+        assert_eq!(spread, one_line);
+        assert_eq!(parse("[1, 2;\n 3, 4]").stmts().len(), 1);
+    }
+}
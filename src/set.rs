@@ -4,19 +4,20 @@ use std::fmt::{self, Debug};
 use std::hash::{DefaultHasher, Hash, Hasher};
 use std::rc::Rc;
 
-use num::bigint::{self, Sign};
-use num::{BigInt, BigRational, Complex, Zero};
+use num::bigint::Sign;
+use num::Zero;
 
-use crate::iter::ValIterator;
-use crate::value::Val;
+use crate::error::{self, RuntimeError};
+use crate::iter::{FiniteIterator, InfiniteIterator, ValIterator};
+use crate::value::{Func, NumVal, Tuple, Val};
 
 pub trait Set {
     fn is_finite(&self) -> bool;
     fn is_countable(&self) -> bool;
 
     /// Enumerates the set into values. If it cannot be enumerated, it returns [`None`].
-    fn enumerate<I: ValIterator>(&self) -> Option<I>;
-    fn contains(&self, other: &Box<dyn Val>) -> bool;
+    fn enumerate(&self) -> Option<Box<dyn ValIterator>>;
+    fn contains(&self, other: &dyn Val) -> bool;
 
     /// Checks if `self` is a subset of `other` or they're equal.
     fn is_subset(&self, other: &Rc<CanonSet>) -> bool;
@@ -30,7 +31,10 @@ pub enum CanonSet {
     Intersect(Rc<Self>, Rc<Self>),
     SymDiff(Rc<Self>, Rc<Self>),
     Exclusion(Rc<Self>, Rc<Self>),
-    Complement(Rc<Self>)
+    Complement(Rc<Self>),
+    /// The Cartesian power `A ^ n`, the set of `n`-tuples of elements of `A`. It is only kept unexpanded for an infinite `A`.
+    Power(Rc<Self>, usize),
+    Predicate(PredicateSet)
 }
 
 impl fmt::Display for CanonSet {
@@ -38,19 +42,155 @@ impl fmt::Display for CanonSet {
         match self {
             Self::Finite(set) => write!(f, "{}", set),
             Self::Infinite(set) => write!(f, "{}", set),
-            Self::Union(a, b) => write!(f, "{} | {}", a, b),
-            Self::Intersect(a, b) => write!(f, "{} & {}", a, b),
-            Self::SymDiff(a, b) => write!(f, "{} ~ {}", a, b),
-            Self::Exclusion(a, b) => write!(f, "{} \\ {}", a, b),
-            Self::Complement(set) => write!(f, "~{}", set)
+            Self::Union(a, b) => a.fmt_operation(f, "|", b),
+            Self::Intersect(a, b) => a.fmt_operation(f, "&", b),
+            Self::SymDiff(a, b) => a.fmt_operation(f, "~", b),
+            Self::Exclusion(a, b) => a.fmt_operation(f, "\\", b),
+            Self::Complement(set) => {
+                write!(f, "~")?;
+                set.fmt_operand(f)
+            }
+            Self::Power(set, n) => {
+                set.fmt_operand(f)?;
+                write!(f, " ^ {n}")
+            }
+            Self::Predicate(set) => write!(f, "{}", set)
         }
     }
 }
 
 /// Logic to canonicalize the set expression tree
 pub fn canon(set: Rc<CanonSet>) -> Rc<CanonSet> {
-    // placeholder for now
-    set
+    match set.as_ref() {
+        // A | A = A, A & A = A
+        CanonSet::Union(a, b) |
+        CanonSet::Intersect(a, b) if a == b => Rc::clone(a),
+
+        // A \ A = Empty, A ~ A = Empty
+        CanonSet::Exclusion(a, b) |
+        CanonSet::SymDiff(a, b) if a == b => Rc::new(CanonSet::empty()),
+
+        // ~~A = A, ~Univ = Empty
+        CanonSet::Complement(a) => match a.as_ref() {
+            CanonSet::Complement(inner) => Rc::clone(inner),
+            CanonSet::Infinite(InfiniteSet::Univ) => Rc::new(CanonSet::empty()),
+            _ => set
+        }
+
+        // Operations on finite sets are computed right away
+        _ => if let Some(computed) = compute_finite(&set) {
+            Rc::new(CanonSet::Finite(computed))
+        } else {
+            set
+        }
+    }
+}
+
+/// Computes a set operation whose operands are both finite. If either isn't, it returns [`None`].
+fn compute_finite(set: &CanonSet) -> Option<FiniteSet> {
+    let (CanonSet::Union(a, b) | CanonSet::Intersect(a, b) | CanonSet::Exclusion(a, b) | CanonSet::SymDiff(a, b)) = set else {
+        return None
+    };
+
+    // A finite set minus any other set keeps only what the other doesn't contain (like `{1, 2} \ Nat = Empty`).
+    // If a predicate fails on one of its elements, the operation is left as it is, so the error comes up when membership is checked
+    if let (CanonSet::Exclusion(_, _), CanonSet::Finite(a), false) = (set, a.as_ref(), matches!(b.as_ref(), CanonSet::Finite(_))) {
+        return a.try_filter(|element| Ok(!b.try_contains(element.as_ref())?)).ok()
+    }
+
+    // Likewise for an intersection, where either side may be the finite one
+    if let CanonSet::Intersect(_, _) = set {
+        match (a.as_ref(), b.as_ref()) {
+            (CanonSet::Finite(_), CanonSet::Finite(_)) => (),
+            (CanonSet::Finite(finite), other) |
+            (other, CanonSet::Finite(finite)) => return finite.try_filter(|element| other.try_contains(element.as_ref())).ok(),
+            _ => ()
+        }
+    }
+
+    let (CanonSet::Finite(a), CanonSet::Finite(b)) = (a.as_ref(), b.as_ref()) else {
+        return None
+    };
+
+    match set {
+        CanonSet::Union(_, _) => Some(a.union(b)),
+        CanonSet::Intersect(_, _) => Some(a.intersection(b)),
+        CanonSet::Exclusion(_, _) => Some(a.difference(b)),
+        CanonSet::SymDiff(_, _) => Some(a.symmetric_difference(b)),
+        _ => None
+    }
+}
+
+impl CanonSet {
+    /// Creates the empty set
+    pub fn empty() -> Self {
+        Self::Finite(FiniteSet::new(HashSet::new()))
+    }
+
+    /// Checks if `other` is an element, which fails if a predicate set's function does.
+    ///
+    /// Unions and intersections only check their right operand if the left one doesn't already decide membership, so a predicate set on the right isn't called needlessly.
+    pub fn try_contains(&self, other: &dyn Val) -> error::Result<bool> {
+        Ok(match self {
+            Self::Finite(set) => set.contains(other),
+            Self::Infinite(set) => set.contains(other),
+            Self::Union(a, b) => a.try_contains(other)? || b.try_contains(other)?,
+            Self::Intersect(a, b) => a.try_contains(other)? && b.try_contains(other)?,
+            Self::SymDiff(a, b) => a.try_contains(other)? != b.try_contains(other)?,
+            Self::Exclusion(a, b) => a.try_contains(other)? && !b.try_contains(other)?,
+            Self::Complement(set) => !set.try_contains(other)?,
+            Self::Power(set, n) => match other.downcast_ref::<Tuple>() {
+                Some(Tuple(elements)) if elements.len() == *n => {
+                    for element in elements {
+                        if !set.try_contains(element.as_ref())? {
+                            return Ok(false)
+                        }
+                    }
+
+                    true
+                }
+                _ => false
+            }
+            Self::Predicate(set) => set.contains(other)?
+        })
+    }
+
+    /// Checks if `self` and `other` share no elements. If that can't be decided, it returns [`None`].
+    pub fn disjoint(&self, other: &Self) -> Option<bool> {
+        match (self, other) {
+            (Self::Finite(a), Self::Finite(b)) => Some(a.elements.is_disjoint(&b.elements)),
+
+            // Filter the finite side by membership in the other, unless a predicate fails on one of its elements
+            (Self::Finite(finite), set @ (Self::Infinite(_) | Self::Complement(_) | Self::Predicate(_))) |
+            (set @ (Self::Infinite(_) | Self::Complement(_) | Self::Predicate(_)), Self::Finite(finite)) => {
+                for element in &finite.elements {
+                    if set.try_contains(element.as_ref()).ok()? {
+                        return Some(false)
+                    }
+                }
+
+                Some(true)
+            }
+
+            (Self::Infinite(a), Self::Infinite(b)) => Some(a.disjoint(b)),
+
+            _ => None
+        }
+    }
+
+    /// Formats `self op other`, wrapping either side in parentheses if it is itself an operation.
+    fn fmt_operation(&self, f: &mut fmt::Formatter<'_>, op: &str, other: &Self) -> fmt::Result {
+        self.fmt_operand(f)?;
+        write!(f, " {op} ")?;
+        other.fmt_operand(f)
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Finite(_) | Self::Infinite(_) | Self::Complement(_) | Self::Predicate(_) => write!(f, "{self}"),
+            _ => write!(f, "({self})")
+        }
+    }
 }
 
 impl Val for Rc<CanonSet> {
@@ -66,6 +206,10 @@ impl Val for Rc<CanonSet> {
         self.hash(&mut state);
     }
 
+    fn type_name(&self) -> &'static str {
+        "Set"
+    }
+
     fn is_set(&self) -> bool {
         true
     }
@@ -85,7 +229,20 @@ impl Set for CanonSet {
             Self::Finite(set) => set.is_finite(), 
             Self::Infinite(set) => set.is_finite(),
 
-            _ => todo!()
+            // The complement is relative to Univ, so anything but `~Univ` (which canonicalizes to Empty) still leaves an uncountable set
+            Self::Complement(_) => false,
+
+            // Nothing is known about which values satisfy the predicate, but they are all in its domain
+            Self::Predicate(set) => set.domain().is_finite(),
+
+            Self::Power(set, n) => *n == 0 || set.is_finite(),
+
+            Self::Union(a, b) |
+            Self::SymDiff(a, b) => a.is_finite() && b.is_finite(),
+
+            // Either side bounds an intersection, but only the left one bounds a difference
+            Self::Intersect(a, b) => a.is_finite() || b.is_finite(),
+            Self::Exclusion(a, _) => a.is_finite()
         }
     }
 
@@ -93,27 +250,60 @@ impl Set for CanonSet {
         match self {
             Self::Finite(set) => set.is_countable(),
             Self::Infinite(set) => set.is_countable(),
-
-            _ => todo!()
+            Self::Complement(_) => false,
+            Self::Predicate(set) => set.domain().is_countable(),
+            Self::Power(set, n) => *n == 0 || set.is_countable(),
+            Self::Union(a, b) |
+            Self::SymDiff(a, b) => a.is_countable() && b.is_countable(),
+            Self::Intersect(a, b) => a.is_countable() || b.is_countable(),
+            Self::Exclusion(a, _) => a.is_countable()
         }
     }
 
-    fn enumerate<I: ValIterator>(&self) -> Option<I> {
-        todo!()
-    }
-
-    fn contains(&self, other: &Box<dyn Val>) -> bool {
+    fn enumerate(&self) -> Option<Box<dyn ValIterator>> {
         match self {
-            Self::Finite(set) => set.contains(other),
-            Self::Infinite(set) => set.contains(other),
+            Self::Finite(set) => set.enumerate(),
+            Self::Infinite(set) => set.enumerate(),
 
-            _ => todo!()
+            // Operations on finite sets are already computed, so these involve an infinite or predicate set
+            _ => None
         }
     }
 
+    /// Is [`CanonSet::try_contains`], where a predicate that fails counts as not holding the value.
+    fn contains(&self, other: &dyn Val) -> bool {
+        self.try_contains(other).unwrap_or(false)
+    }
+
+    /// Subsets are decided by splitting up set operations until they can be checked directly.
+    /// 
+    /// Some cases can't be decided that way (like whether one predicate set is in another), and those return `false`.
     fn is_subset(&self, other: &Rc<Self>) -> bool {
-        match self {
-            _ => todo!()
+        if self == other.as_ref() {
+            return true
+        }
+
+        match (self, other.as_ref()) {
+            // Each element can be checked on its own
+            (Self::Finite(set), _) => set.is_subset(other),
+            (_, Self::Infinite(InfiniteSet::Univ)) => true,
+
+            (_, Self::Intersect(a, b)) => self.is_subset(a) && self.is_subset(b),
+            (Self::Union(a, b), _) => a.is_subset(other) && b.is_subset(other),
+            // A ~ B is within A | B
+            (Self::SymDiff(a, b), _) => a.is_subset(other) && b.is_subset(other),
+            (Self::Intersect(a, b), _) => a.is_subset(other) || b.is_subset(other),
+            (Self::Exclusion(a, _), _) => a.is_subset(other),
+
+            (Self::Complement(a), Self::Complement(b)) => b.is_subset(a),
+            (_, Self::Complement(b)) => self.disjoint(b) == Some(true),
+            (_, Self::Exclusion(a, b)) => self.is_subset(a) && self.disjoint(b) == Some(true),
+            (_, Self::Union(a, b)) => self.is_subset(a) || self.is_subset(b),
+
+            (Self::Power(a, n), Self::Power(b, m)) => n == m && a.is_subset(b),
+
+            (Self::Infinite(set), _) => set.is_subset(other),
+            _ => false
         }
     }
 }
@@ -121,7 +311,7 @@ impl Set for CanonSet {
 /// A finite set that holds all of its elements
 /// 
 /// It saves its hash on creation, as all values are immutable so it will never change. That way it doesn't have to rehash every time it needs a hash.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Eq)]
 pub struct FiniteSet {
     elements: HashSet<Box<dyn Val>>,
     hash: u64
@@ -140,6 +330,84 @@ impl FiniteSet {
 
         base
     }
+
+    pub fn len(&self) -> usize {
+        self.elements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Compares every element, without first checking the saved hashes like `==` does.
+    pub fn has_same_elements(&self, other: &Self) -> bool {
+        self.elements == other.elements
+    }
+
+    /// Returns the elements ordered by `total_cmp`, which is how the set is displayed.
+    pub fn sorted(&self) -> Vec<&dyn Val> {
+        let mut elements = self.elements.iter().map(|element| element.as_ref()).collect::<Vec<_>>();
+        elements.sort_by(|a, b| a.total_cmp(*b));
+
+        elements
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        Self::new(self.elements.union(&other.elements).cloned().collect())
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::new(self.elements.intersection(&other.elements).cloned().collect())
+    }
+
+    /// Returns the elements of `self` that aren't in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        Self::new(self.elements.difference(&other.elements).cloned().collect())
+    }
+
+    /// Returns the elements for which `pred` is true, stopping at the first error.
+    pub fn try_filter<P: FnMut(&Box<dyn Val>) -> error::Result<bool>>(&self, mut pred: P) -> error::Result<Self> {
+        let mut kept = HashSet::new();
+
+        for element in &self.elements {
+            if pred(element)? {
+                kept.insert(element.clone());
+            }
+        }
+
+        Ok(Self::new(kept))
+    }
+
+    /// Returns the set of `n`-tuples of elements of `self`, so `n = 0` gives just the empty tuple.
+    pub fn cartesian_power(&self, n: usize) -> Self {
+        let mut tuples = vec![vec![]];
+
+        for _ in 0..n {
+            tuples = tuples
+                .into_iter()
+                .flat_map(|tuple: Vec<Box<dyn Val>>| self.elements.iter().map(move |element| {
+                    let mut tuple = tuple.clone();
+                    tuple.push(element.clone());
+
+                    tuple
+                }))
+                .collect();
+        }
+
+        Self::new(tuples.into_iter().map(|tuple| Box::new(Tuple(tuple)) as Box<dyn Val>).collect())
+    }
+
+    /// Returns the elements in exactly one of `self` and `other`, i.e. `(self \ other) | (other \ self)`.
+    pub fn symmetric_difference(&self, other: &Self) -> Self {
+        Self::new(self.elements.symmetric_difference(&other.elements).cloned().collect())
+    }
+}
+
+impl PartialEq for FiniteSet {
+    /// Sets with different saved hashes can't be equal, so they are rejected without comparing elements. Equal hashes still need the full comparison.
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.has_same_elements(other)
+    }
 }
 
 impl Hash for FiniteSet {
@@ -171,7 +439,7 @@ impl fmt::Display for FiniteSet {
         write!(f, "{{")?;
         
         let mut i = self.elements.len();
-        for element in self.elements.iter() {
+        for element in self.sorted() {
             if i > 1 {
                 write!(f, "{}, ", element)?;
             } else {
@@ -194,21 +462,93 @@ impl Set for FiniteSet {
         true
     }
 
-    fn enumerate<I: ValIterator>(&self) -> Option<I> {
-        todo!()
+    fn enumerate(&self) -> Option<Box<dyn ValIterator>> {
+        Some(Box::new(FiniteIterator::from(self)))
     }
 
-    fn contains(&self, other: &Box<dyn Val>) -> bool {
+    fn contains(&self, other: &dyn Val) -> bool {
         self.elements.contains(other)
     }
 
     fn is_subset(&self, other: &Rc<CanonSet>) -> bool {
         match other.as_ref() {
-            CanonSet::Finite(set) => self == set,
-            
-            _ => todo!()
+            CanonSet::Finite(set) => self.elements.is_subset(&set.elements),
+            _ => self.elements.iter().all(|element| other.contains(element.as_ref()))
+        }
+    }
+}
+
+/// A set defined by a unary function returning a bool, holding exactly the values it maps to `true`.
+/// 
+/// Two predicate sets are equal if their functions are, so `setof(x -> x > 1)` is the same set wherever it is written.
+#[derive(Debug, Clone)]
+pub struct PredicateSet {
+    pred: Rc<Func>
+}
+
+impl PredicateSet {
+    pub fn new(pred: Func) -> Self {
+        Self {
+            pred: Rc::new(pred)
         }
     }
+
+    /// Returns the set the predicate's argument must be in, which holds every member.
+    pub fn domain(&self) -> Rc<CanonSet> {
+        self.pred
+            .arg_types()
+            .pop()
+            .unwrap_or_else(|| Rc::new(CanonSet::Infinite(InfiniteSet::Univ)))
+    }
+
+    /// Returns the members, if its domain is a finite set, by checking each element of the domain.
+    pub fn members(&self) -> error::Result<Option<FiniteSet>> {
+        let domain = self.domain();
+        let CanonSet::Finite(domain) = domain.as_ref() else {
+            return Ok(None)
+        };
+
+        let mut members = HashSet::new();
+
+        for element in domain.sorted() {
+            if self.contains(element)? {
+                members.insert(element.clone_box());
+            }
+        }
+
+        Ok(Some(FiniteSet::new(members)))
+    }
+
+    /// Calls the predicate on `other`, which fails if the call does, or if it doesn't return a bool.
+    fn contains(&self, other: &dyn Val) -> error::Result<bool> {
+        let result = self.pred.call(&[Some(other.clone_box())])?;
+
+        if let Some(&is_member) = result.downcast_ref::<bool>() {
+            Ok(is_member)
+        } else {
+            Err(RuntimeError::new(format!("Predicate '{}' must return a bool, but returned '{result}' for '{other}'", self.pred)).into())
+        }
+    }
+}
+
+impl PartialEq for PredicateSet {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.pred, &other.pred) || self.pred.compare(other.pred.as_ref())
+    }
+}
+
+impl Eq for PredicateSet {}
+
+impl Hash for PredicateSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.pred.hash(state);
+    }
+}
+
+impl fmt::Display for PredicateSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "setof({})", self.pred)
+    }
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -232,6 +572,29 @@ impl InfiniteSet {
             Self::Str => "Str"
         })
     }
+
+    /// Returns where the set is in the chain of numeric sets (`Nat` in `Int` in `Real` in `Complex`), if it is numeric.
+    fn numeric_rank(&self) -> Option<u8> {
+        match self {
+            Self::Nat => Some(0),
+            Self::Int => Some(1),
+            Self::Real => Some(2),
+            Self::Complex => Some(3),
+            _ => None
+        }
+    }
+
+    /// Checks if `self` and `other` share no elements.
+    /// 
+    /// The numeric sets form a chain (`Nat` in `Int` in `Real` in `Complex`), so any two of them overlap, and none of them overlap `Str`.
+    pub fn disjoint(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Univ, _) | (_, Self::Univ) => false,
+            (Self::Str, Self::Str) => false,
+            (Self::Str, _) | (_, Self::Str) => true,
+            _ => false
+        }
+    }
 }
 
 impl fmt::Display for InfiniteSet {
@@ -246,97 +609,70 @@ impl Set for InfiniteSet {
     }
 
     fn is_countable(&self) -> bool {
-        match self {
-            Self::Nat |
-            Self::Int |
-            Self::Str => true,
-
-            _ => false
-        }
+        matches!(self, Self::Nat | Self::Int | Self::Str)
     }
 
-    fn enumerate<I: ValIterator>(&self) -> Option<I> {
+    fn enumerate(&self) -> Option<Box<dyn ValIterator>> {
         match self {
-            _ => todo!()
+            Self::Nat => Some(Box::new(InfiniteIterator::nat())),
+            Self::Int => Some(Box::new(InfiniteIterator::int())),
+
+            // Real and Complex are uncountable, and there is no order to enumerate the rest in yet
+            _ => None
         }
     }
 
-    fn contains(&self, other: &Box<dyn Val>) -> bool {
+    fn contains(&self, other: &dyn Val) -> bool {
+        // Though bools are 0 and 1 in arithmetic, they aren't numbers, just as `true == 1` is false
+        let number = if other.is_num() { NumVal::from_val(other) } else { None };
+
         match self {
             Self::Univ => true,
-            Self::Nat => if other.is_num() {
-                if let Some(bigint) = other.downcast_ref::<BigInt>() {
-                    bigint.sign() != Sign::Minus
-                } else if let Some(bigrat) = other.downcast_ref::<BigRational>() {
-                    bigrat.is_integer() && bigrat.numer().sign() != Sign::Minus
-                } else if let Some(complex) = other.downcast_ref::<Complex<BigRational>>() {
-                    complex.im == BigRational::zero() && complex.re.is_integer() && complex.re.numer().sign() != Sign::Minus
-                } else if let Some(_) = other.downcast_ref::<bool>() {
-                    true
-                } else {
-                    false
-                }
-            } else {
-                false
+            // Numbers are checked by value, whatever their representation, so `3+0i` is in `Nat` and `-4/2` is in `Int`
+            Self::Nat => match number {
+                Some(NumVal::Int(int)) => int.sign() != Sign::Minus,
+                Some(NumVal::Real(real)) => real.is_integer() && real.numer().sign() != Sign::Minus,
+                Some(NumVal::Complex(complex)) => complex.im.is_zero() && complex.re.is_integer() && complex.re.numer().sign() != Sign::Minus,
+                None => false
             }
 
-            Self::Int => if other.is_num() {
-                if let Some(_) = other.downcast_ref::<BigInt>() {
-                    true
-                } else if let Some(bigrat) = other.downcast_ref::<BigRational>() {
-                    bigrat.is_integer()
-                } else if let Some(complex) = other.downcast_ref::<Complex<BigRational>>() {
-                    complex.im == BigRational::zero() && complex.re.is_integer()
-                } else if let Some(_) = other.downcast_ref::<bool>() {
-                    true
-                } else {
-                    false
-                }
-            } else {
-                false
+            Self::Int => match number {
+                Some(NumVal::Int(_)) => true,
+                Some(NumVal::Real(real)) => real.is_integer(),
+                Some(NumVal::Complex(complex)) => complex.im.is_zero() && complex.re.is_integer(),
+                None => false
             }
 
-            Self::Real => if other.is_num() {
-                if let Some(_) = other.downcast_ref::<BigInt>() {
-                    true
-                } else if let Some(_) = other.downcast_ref::<BigRational>() {
-                    true
-                } else if let Some(complex) = other.downcast_ref::<Complex<BigRational>>() {
-                    complex.im == BigRational::zero()
-                } else if let Some(_) = other.downcast_ref::<bool>() {
-                    true
-                } else {
-                    false
-                }
-            } else {
-                false
+            Self::Real => match number {
+                Some(NumVal::Int(_) | NumVal::Real(_)) => true,
+                Some(NumVal::Complex(complex)) => complex.im.is_zero(),
+                None => false
             }
 
-            Self::Complex => other.is_num(), // as of now, Complex is the all-encompassing numeric type. Perhaps in future this will be changed. Perhaps a Num class or smth. Also, there may be other number types as well, like Alg, Even, Odd, etc.
+            Self::Complex => number.is_some(), // as of now, Complex is the all-encompassing numeric type. Perhaps in future this will be changed. Perhaps a Num class or smth. Also, there may be other number types as well, like Alg, Even, Odd, etc.
 
             Self::Str => if other.is_str() {
-                if let Some(_) = other.downcast_ref::<String>() {
-                    true
-                } else {
-                    false
-                }
+                other.downcast_ref::<String>().is_some()
             } else {
                 // todo, allow for casting to string
                 // perhapse, can_str for can be casted to str
                 false
             }
-            _ => todo!()
         }
     }
 
     fn is_subset(&self, other: &Rc<CanonSet>) -> bool {
-        todo!()
+        match other.as_ref() {
+            CanonSet::Infinite(set) => self == set || *set == Self::Univ || matches!(
+                (self.numeric_rank(), set.numeric_rank()),
+                (Some(rank), Some(other_rank)) if rank <= other_rank
+            ),
+            _ => false
+        }
     }
-
-
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct SetPool {
     pool: HashSet<Rc<CanonSet>>
 }
@@ -350,10 +686,131 @@ impl SetPool {
 
     /// Interns the given [`Rc<Set>`] and returns it back out. If it is new, it will intern it to the [`SetPool`], otherwise it will just return it
     pub fn intern(&mut self, set: &Rc<CanonSet>) -> Rc<CanonSet> {
-        if !self.pool.contains(set) {
-            self.pool.insert(Rc::clone(set));
+        if let Some(interned) = self.pool.get(set) {
+            return Rc::clone(interned);
         }
 
+        self.pool.insert(Rc::clone(set));
+
         Rc::clone(set)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use num::{BigInt, BigRational, Complex};
+
+    use super::*;
+
+    fn nat() -> Rc<CanonSet> {
+        Rc::new(CanonSet::Infinite(InfiniteSet::Nat))
+    }
+
+    fn real() -> Rc<CanonSet> {
+        Rc::new(CanonSet::Infinite(InfiniteSet::Real))
+    }
+
+    fn finite(elements: &[i64]) -> Rc<CanonSet> {
+        Rc::new(CanonSet::Finite(FiniteSet::new(elements
+            .iter()
+            .map(|&n| Box::new(BigInt::from(n)) as Box<dyn Val>)
+            .collect())))
+    }
+
+    #[test]
+    fn union_is_only_as_small_as_its_largest_operand() {
+        let union = CanonSet::Union(nat(), finite(&[-1]));
+        assert!(!union.is_finite());
+        assert!(union.is_countable());
+
+        let union = CanonSet::Union(nat(), real());
+        assert!(!union.is_countable());
+    }
+
+    #[test]
+    fn intersection_is_bounded_by_either_operand() {
+        let intersection = CanonSet::Intersect(real(), nat());
+        assert!(!intersection.is_finite());
+        assert!(intersection.is_countable());
+
+        assert!(CanonSet::Intersect(real(), finite(&[1])).is_finite());
+    }
+
+    #[test]
+    fn exclusion_is_bounded_by_its_left_operand() {
+        assert!(CanonSet::Exclusion(finite(&[1]), real()).is_finite());
+        assert!(!CanonSet::Exclusion(real(), nat()).is_countable());
+        assert!(CanonSet::Exclusion(nat(), real()).is_countable());
+    }
+
+    #[test]
+    fn symmetric_difference_needs_both_operands() {
+        assert!(CanonSet::SymDiff(finite(&[1]), finite(&[2])).is_finite());
+        assert!(!CanonSet::SymDiff(finite(&[1]), nat()).is_finite());
+        assert!(!CanonSet::SymDiff(nat(), real()).is_countable());
+    }
+
+    #[test]
+    fn membership_of_each_representation_in_each_builtin_set() {
+        let rational = |numer: i64, denom: i64| BigRational::new(BigInt::from(numer), BigInt::from(denom));
+        let complex = |re: i64, im: i64| Complex::new(rational(re, 1), rational(im, 1));
+
+        // Each value, and whether it is in Nat, Int, Real, Complex, and Str
+        let values: [(Box<dyn Val>, [bool; 5]); 13] = [
+            (Box::new(BigInt::from(3)), [true, true, true, true, false]),
+            (Box::new(BigInt::from(-2)), [false, true, true, true, false]),
+            (Box::new(rational(6, 2)), [true, true, true, true, false]),
+            (Box::new(rational(-4, 2)), [false, true, true, true, false]),
+            (Box::new(rational(1, 2)), [false, false, true, true, false]),
+            (Box::new(complex(3, 0)), [true, true, true, true, false]),
+            (Box::new(complex(-2, 0)), [false, true, true, true, false]),
+            (Box::new(Complex::new(rational(1, 2), rational(0, 1))), [false, false, true, true, false]),
+            (Box::new(complex(3, 1)), [false, false, false, true, false]),
+            (Box::new(true), [false; 5]),
+            (Box::new(false), [false; 5]),
+            (Box::new(String::from("3")), [false, false, false, false, true]),
+            (Box::new(Tuple(vec![Box::new(BigInt::from(3))])), [false; 5])
+        ];
+        let sets = [InfiniteSet::Nat, InfiniteSet::Int, InfiniteSet::Real, InfiniteSet::Complex, InfiniteSet::Str];
+
+        for (value, expected) in &values {
+            for (set, expected) in sets.iter().zip(expected) {
+                assert_eq!(set.contains(value.as_ref()), *expected, "checking if '{value}' is in '{set}'");
+            }
+
+            assert!(InfiniteSet::Univ.contains(value.as_ref()));
+        }
+    }
+
+    #[test]
+    fn finite_intersection_is_computed() {
+        let intersection = canon(Rc::new(CanonSet::Intersect(nat(), finite(&[1, -1]))));
+        assert_eq!(intersection, finite(&[1]));
+    }
+
+    #[test]
+    fn complement_of_a_finite_set() {
+        let complement = CanonSet::Complement(finite(&[1, 2, 3]));
+
+        assert!(complement.contains(&BigInt::from(5)));
+        assert!(complement.contains(&String::from("2")));
+        assert!(!complement.contains(&BigInt::from(2)));
+        assert!(!complement.contains(&BigRational::new(BigInt::from(4), BigInt::from(2))));
+
+        assert!(!complement.is_finite());
+        assert!(!complement.is_countable());
+        assert!(complement.enumerate().is_none());
+    }
+
+    #[test]
+    fn finite_set_enumerates_in_sorted_order() {
+        let set = finite(&[3, 1, 2, -5, 10]);
+        let enumerate = || set.enumerate().unwrap().map(|element| element.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(enumerate(), ["-5", "1", "2", "3", "10"]);
+        assert_eq!(enumerate(), enumerate());
+
+        // A set built in another order is enumerated the same way
+        assert_eq!(finite(&[10, -5, 2, 1, 3]).enumerate().unwrap().map(|element| element.to_string()).collect::<Vec<_>>(), enumerate());
+    }
+}
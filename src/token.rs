@@ -1,12 +1,13 @@
 #[derive(Debug, Clone)]
 pub struct Token {
     kind: TokenKind,
-    line: usize
+    line: usize,
+    col: usize
 }
 
 impl Token {
-    pub fn new(kind: TokenKind, line: usize) -> Self {
-        Self { kind, line }
+    pub fn new(kind: TokenKind, line: usize, col: usize) -> Self {
+        Self { kind, line, col }
     }
 
     pub fn kind(&self) -> &TokenKind {
@@ -42,10 +43,12 @@ impl Token {
             TokenKind::Greater => ">",
             TokenKind::GreaterColon => ">:",
             TokenKind::GreaterEq => ">=",
+            TokenKind::GreaterEqColon => ">=:",
             TokenKind::Hash => "#",
             TokenKind::Less => "<",
             TokenKind::LessColon => "<:",
             TokenKind::LessEq => "<=",
+            TokenKind::LessEqColon => "<=:",
             TokenKind::Minus => "-",
             TokenKind::OpenBrace => "{",
             TokenKind::OpenBracket => "[",
@@ -75,18 +78,25 @@ impl Token {
     pub fn line(&self) -> usize {
         self.line
     }
+
+    /// The 1-based column of the token's first character.
+    pub fn col(&self) -> usize {
+        self.col
+    }
 }
 
 impl Default for Token {
     fn default() -> Self {
         Self {
             kind: TokenKind::EOL,
-            line: 0
+            line: 0,
+            col: 0
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum TokenKind {
     // Single-Character Tokens
     Plus, Minus, Star, Slash, Caret, Eq, Bang, Tilde, 
@@ -102,6 +112,9 @@ pub enum TokenKind {
     SmallArrow, FatArrow,
     DblDot,
 
+    // Triple-Character Tokens
+    LessEqColon, GreaterEqColon,
+
     // Value Tokens
     Ident(String), String(String), Char(String),
     Number(String), 
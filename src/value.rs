@@ -1,25 +1,40 @@
-use std::any::Any;
+use std::any::{Any, TypeId};
 use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fmt::{self, Debug, Display};
 use std::hash::{Hash, Hasher};
 use std::rc::Rc;
-use num::{BigInt, BigRational, Complex};
+use num::{BigInt, BigRational, Complex, ToPrimitive, Zero};
 
 use crate::ast::expr::{self, Expr};
 use crate::environment::{Env, SymStore};
-use crate::interpreter::Interpreter;
-use crate::set::{CanonSet, Set};
+use crate::error::{self, RuntimeError};
+use crate::interpreter::{Interpreter, Settings};
+use crate::set::CanonSet;
 
 pub trait Val: Any + Debug + Display + CloneBox {
     fn compare(&self, other: &dyn Val) -> bool;
     fn hash_val(&self, state: &mut dyn Hasher);
 
+    /// The name of the value's type in error messages, like `Int` or `Str`.
+    fn type_name(&self) -> &'static str;
+
     fn is_num(&self) -> bool { false }
     fn is_str(&self) -> bool { false }
     fn is_tup(&self) -> bool { false }
     fn is_mat(&self) -> bool { false }
     fn is_set(&self) -> bool { false }
 
+    /// Where the type is ordered among other types: bools, numbers, strings, tuples, then everything else.
+    fn type_rank(&self) -> u8 { 4 }
+
+    /// Orders the value against `other`. Values of different types are ordered by [`Val::type_rank`], and values with no order between
+    /// them (like `1+i` and `2`, or two sets) are [`None`].
+    fn cmp_val(&self, other: &dyn Val) -> Option<Ordering> {
+        cmp_rank(self.type_rank(), other)
+    }
+
     fn as_any(&self) -> &dyn Any;
     fn as_boxed_any(&self) -> Box<dyn Any>;
 }
@@ -33,11 +48,130 @@ impl dyn Val {
         self.as_boxed_any().downcast::<T>()
     }
 
+    /// Unboxes the value if it is numeric (see [`NumVal::from_val`]).
+    pub fn as_number(&self) -> Option<NumVal> {
+        NumVal::from_val(self)
+    }
+
+    /// Returns the value as an integer, if it is one by value, so `4/2` and `3+0i` both work.
+    pub fn as_bigint(&self) -> Option<BigInt> {
+        match self.as_number()? {
+            NumVal::Int(int) => Some(int),
+            NumVal::Real(real) if real.is_integer() => Some(real.to_integer()),
+            NumVal::Complex(complex) if complex.im.is_zero() && complex.re.is_integer() => Some(complex.re.to_integer()),
+            _ => None
+        }
+    }
+
+    /// Returns the value as a rational, if it is real by value, so `3+0i` works.
+    pub fn as_bigrational(&self) -> Option<BigRational> {
+        match self.as_number()? {
+            NumVal::Int(int) => Some(BigRational::from(int)),
+            NumVal::Real(real) => Some(real),
+            NumVal::Complex(complex) if complex.im.is_zero() => Some(complex.re),
+            _ => None
+        }
+    }
+
+    /// Returns the nearest float to the value, if it is real by value and within the range of floats. Unlike the other conversions, this loses precision.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_bigrational()?.to_f64().filter(|float| float.is_finite())
+    }
+
+    /// Like [`Val::compare`], but values of different types (like `2` and `2.0`) are never equal, even inside tuples and matrices.
+    pub fn compare_strict(&self, other: &dyn Val) -> bool {
+        if self.as_any().type_id() != other.as_any().type_id() {
+            return false
+        }
+
+        if let (Some(l_tup), Some(r_tup)) = (self.downcast_ref::<Tuple>(), other.downcast_ref::<Tuple>()) {
+            l_tup.0.len() == r_tup.0.len() && l_tup.0.iter().zip(&r_tup.0).all(|(l, r)| l.compare_strict(r.as_ref()))
+        } else if let (Some(l_mat), Some(r_mat)) = (self.downcast_ref::<Matrix>(), other.downcast_ref::<Matrix>()) {
+            l_mat.dims() == r_mat.dims() && l_mat.elements.iter().zip(&r_mat.elements).all(|(l, r)| l.compare_strict(r.as_ref()))
+        } else {
+            self.compare(other)
+        }
+    }
+
+    /// Orders any two values, so that sets are shown (and enumerated) the same way every time.
+    ///
+    /// This is [`Val::cmp_val`], wherever it gives an order. Otherwise, numbers are ordered by their real part, then by their imaginary part,
+    /// tuples element by element, and anything else by how it displays.
+    pub fn total_cmp(&self, other: &dyn Val) -> Ordering {
+        if let Some(ordering) = self.cmp_val(other) {
+            return ordering
+        }
+
+        if let (Some(NumVal::Complex(l)), Some(NumVal::Complex(r))) = (
+            NumVal::from_val(self).map(|num| num.promote(NumKind::Complex)),
+            NumVal::from_val(other).map(|num| num.promote(NumKind::Complex))
+        ) {
+            l.re.cmp(&r.re).then_with(|| l.im.cmp(&r.im))
+        } else if let (Some(Tuple(l_tup)), Some(Tuple(r_tup))) = (self.downcast_ref(), other.downcast_ref()) {
+            l_tup
+                .iter()
+                .zip(r_tup)
+                .map(|(l, r)| l.total_cmp(r.as_ref()))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or_else(|| l_tup.len().cmp(&r_tup.len()))
+        } else {
+            self.to_string().cmp(&other.to_string())
+        }
+    }
+
+    /// Formats the value. Numbers with thousands of digits are slow to format, so their strings are remembered and reused.
     pub fn display(&self) -> String {
-        format!("{self}")
+        if !self.is_costly_to_display() {
+            return format!("{self}")
+        }
+
+        // Equal numbers can have different representations (`2` and `2+0i`), which don't display the same
+        let key = (self.as_any().type_id(), self.clone_box());
+
+        DISPLAY_CACHE.with_borrow_mut(|cache| {
+            if let Some(string) = cache.get(&key) {
+                return string.to_string()
+            }
+
+            if cache.len() >= DISPLAY_CACHE_CAPACITY {
+                cache.clear();
+            }
+
+            let string = Rc::<str>::from(format!("{self}"));
+            cache.insert(key, Rc::clone(&string));
+
+            string.to_string()
+        })
+    }
+
+    fn is_costly_to_display(&self) -> bool {
+        let is_long = |int: &BigInt| int.bits() >= DISPLAY_CACHE_MIN_BITS;
+
+        if let Some(int) = self.downcast_ref::<BigInt>() {
+            is_long(int)
+        } else if let Some(real) = self.downcast_ref::<BigRational>() {
+            is_long(real.numer()) || is_long(real.denom())
+        } else if let Some(complex) = self.downcast_ref::<Complex<BigRational>>() {
+            [&complex.re, &complex.im].iter().any(|part| is_long(part.numer()) || is_long(part.denom()))
+        } else {
+            false
+        }
     }
 }
 
+/// Numbers with at least this many bits (about 1,200 digits) have their display strings cached.
+const DISPLAY_CACHE_MIN_BITS: u64 = 4096;
+
+/// The most display strings kept at once. When it fills up, it starts over.
+const DISPLAY_CACHE_CAPACITY: usize = 64;
+
+/// Display strings keyed by each value and its representation.
+type DisplayCache = HashMap<(TypeId, Box<dyn Val>), Rc<str>>;
+
+thread_local! {
+    static DISPLAY_CACHE: RefCell<DisplayCache> = RefCell::new(HashMap::new());
+}
+
 impl Eq for dyn Val {}
 
 impl Hash for dyn Val {
@@ -71,6 +205,16 @@ impl PartialEq for dyn Val {
     }
 }
 
+/// Orders a value of the given rank against `other` by their types alone, which is [`None`] when they have the same rank.
+fn cmp_rank(rank: u8, other: &dyn Val) -> Option<Ordering> {
+    Some(rank.cmp(&other.type_rank())).filter(|ordering| ordering.is_ne())
+}
+
+/// Orders two numbers by value, so `2` and `2.0` are equal. Complex numbers only have an order if they are real by value.
+fn cmp_num(left: &dyn Val, right: &dyn Val) -> Option<Ordering> {
+    cmp_rank(left.type_rank(), right).or_else(|| Some(left.as_bigrational()?.cmp(&right.as_bigrational()?)))
+}
+
 impl Val for BigInt {
     fn compare(&self, other: &dyn Val) -> bool {
         if let Some(other_int) = other.downcast_ref::<BigInt>() {
@@ -97,10 +241,22 @@ impl Val for BigInt {
         Complex::<BigRational>::from(BigRational::from(self.clone())).hash(&mut state);
     }
 
+    fn type_name(&self) -> &'static str {
+        "Int"
+    }
+
     fn is_num(&self) -> bool {
         true
     }
 
+    fn type_rank(&self) -> u8 {
+        1
+    }
+
+    fn cmp_val(&self, other: &dyn Val) -> Option<Ordering> {
+        cmp_num(self, other)
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -127,10 +283,22 @@ impl Val for BigRational {
         Complex::<BigRational>::from(self.clone()).hash(&mut state);
     }
 
+    fn type_name(&self) -> &'static str {
+        "Real"
+    }
+
     fn is_num(&self) -> bool {
         true
     }
 
+    fn type_rank(&self) -> u8 {
+        1
+    }
+
+    fn cmp_val(&self, other: &dyn Val) -> Option<Ordering> {
+        cmp_num(self, other)
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -157,10 +325,22 @@ impl Val for Complex<BigRational> {
         self.hash(&mut state);
     }
 
+    fn type_name(&self) -> &'static str {
+        "Complex"
+    }
+
     fn is_num(&self) -> bool {
         true
     }
 
+    fn type_rank(&self) -> u8 {
+        1
+    }
+
+    fn cmp_val(&self, other: &dyn Val) -> Option<Ordering> {
+        cmp_num(self, other)
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -183,10 +363,22 @@ impl Val for String {
         self.hash(&mut state);
     }
 
+    fn type_name(&self) -> &'static str {
+        "Str"
+    }
+
     fn is_str(&self) -> bool {
         true
     }
 
+    fn type_rank(&self) -> u8 {
+        2
+    }
+
+    fn cmp_val(&self, other: &dyn Val) -> Option<Ordering> {
+        cmp_rank(2, other).or_else(|| Some(self.cmp(other.downcast_ref::<String>()?)))
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -199,7 +391,7 @@ impl Val for String {
 impl Val for bool {
     fn compare(&self, other: &dyn Val) -> bool {
         if let Some(other_bool) = other.downcast_ref::<bool>() {
-            *self && *other_bool
+            self == other_bool
         } else {
             false
         }
@@ -208,6 +400,18 @@ impl Val for bool {
     fn hash_val(&self, mut state: &mut dyn Hasher) {
         self.hash(&mut state);
     }
+
+    fn type_name(&self) -> &'static str {
+        "Bool"
+    }
+
+    fn type_rank(&self) -> u8 {
+        0
+    }
+
+    fn cmp_val(&self, other: &dyn Val) -> Option<Ordering> {
+        cmp_rank(0, other).or_else(|| Some(self.cmp(other.downcast_ref::<bool>()?)))
+    }
     
     fn as_any(&self) -> &dyn Any {
         self
@@ -218,6 +422,152 @@ impl Val for bool {
     }
 }
 
+/// A numeric value unboxed from a [`Val`], used to do arithmetic on one representation at a time.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NumVal {
+    Int(BigInt),
+    Real(BigRational),
+    Complex(Complex<BigRational>)
+}
+
+/// The representation of a [`NumVal`].
+///
+/// Kinds are ordered by how much they can hold, so the common kind of two values is the greater one (`Int` -> `Real` -> `Complex`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NumKind {
+    Int,
+    Real,
+    Complex
+}
+
+impl NumVal {
+    /// Unboxes a numeric [`Val`]. Booleans are treated as `0` and `1`. If it is not numeric, it returns [`None`].
+    pub fn from_val(val: &dyn Val) -> Option<Self> {
+        if let Some(bigint) = val.downcast_ref::<BigInt>() {
+            Some(Self::Int(bigint.to_owned()))
+        } else if let Some(bigrat) = val.downcast_ref::<BigRational>() {
+            Some(Self::Real(bigrat.to_owned()))
+        } else if let Some(complex) = val.downcast_ref::<Complex<BigRational>>() {
+            Some(Self::Complex(complex.to_owned()))
+        } else {
+            val.downcast_ref::<bool>().map(|&bool| Self::Int(BigInt::from(bool as u8)))
+        }
+    }
+
+    pub fn kind(&self) -> NumKind {
+        match self {
+            Self::Int(_) => NumKind::Int,
+            Self::Real(_) => NumKind::Real,
+            Self::Complex(_) => NumKind::Complex
+        }
+    }
+
+    /// Converts the value to the given [`NumKind`]. It never demotes, so a `kind` lower than the value's own is ignored.
+    pub fn promote(self, kind: NumKind) -> Self {
+        match (self, kind) {
+            (Self::Int(bigint), NumKind::Real) => Self::Real(BigRational::from(bigint)),
+            (Self::Int(bigint), NumKind::Complex) => Self::Complex(Complex::from(BigRational::from(bigint))),
+            (Self::Real(bigrat), NumKind::Complex) => Self::Complex(Complex::from(bigrat)),
+            (num, _) => num
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match self {
+            Self::Int(bigint) => bigint.is_zero(),
+            Self::Real(bigrat) => bigrat.is_zero(),
+            Self::Complex(complex) => complex.is_zero()
+        }
+    }
+}
+
+/// Promotes two numeric [`Val`]s to their common [`NumKind`], and returns both along with that kind.
+///
+/// If either is not numeric, it returns [`None`].
+pub fn coerce_pair(left: &dyn Val, right: &dyn Val) -> Option<(NumVal, NumVal, NumKind)> {
+    let left = NumVal::from_val(left)?;
+    let right = NumVal::from_val(right)?;
+    let kind = left.kind().max(right.kind());
+
+    Some((left.promote(kind), right.promote(kind), kind))
+}
+
+/// The value of an expression with no meaningful result, written `()` or `unit`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Unit;
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "()")
+    }
+}
+
+impl Val for Unit {
+    fn compare(&self, other: &dyn Val) -> bool {
+        other.downcast_ref::<Unit>().is_some()
+    }
+
+    fn hash_val(&self, mut state: &mut dyn Hasher) {
+        self.hash(&mut state);
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Unit"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_boxed_any(&self) -> Box<dyn Any> {
+        Box::new(self.to_owned())
+    }
+}
+
+/// The cardinality of an infinite set, as `#S` of a finite set is just its number of elements.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Cardinality {
+    Countable,
+    /// Countable, but maybe finite, like a predicate set over `Nat`.
+    AtMostCountable,
+    Uncountable,
+    /// Anything from finite to uncountable, like a predicate set over `Real`.
+    Unknown
+}
+
+impl Display for Cardinality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Countable => write!(f, "countably infinite"),
+            Self::AtMostCountable => write!(f, "at most countable"),
+            Self::Uncountable => write!(f, "uncountable"),
+            Self::Unknown => write!(f, "unknown")
+        }
+    }
+}
+
+impl Val for Cardinality {
+    fn compare(&self, other: &dyn Val) -> bool {
+        other.downcast_ref::<Cardinality>() == Some(self)
+    }
+
+    fn hash_val(&self, mut state: &mut dyn Hasher) {
+        self.hash(&mut state);
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Cardinality"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_boxed_any(&self) -> Box<dyn Any> {
+        Box::new(self.to_owned())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Tuple(pub Vec<Box<dyn Val>>);
 
@@ -253,9 +603,33 @@ impl Val for Tuple {
         self.0.hash(&mut state);
     }
 
+    fn type_name(&self) -> &'static str {
+        "Tuple"
+    }
+
     fn is_tup(&self) -> bool {
         true
     }
+
+    fn type_rank(&self) -> u8 {
+        3
+    }
+
+    /// Tuples are ordered element by element, and a tuple comes before any longer tuple it begins.
+    fn cmp_val(&self, other: &dyn Val) -> Option<Ordering> {
+        cmp_rank(3, other).or_else(|| {
+            let Tuple(others) = other.downcast_ref()?;
+
+            for (l, r) in self.0.iter().zip(others) {
+                match l.cmp_val(r.as_ref())? {
+                    Ordering::Equal => (),
+                    ordering => return Some(ordering)
+                }
+            }
+
+            Some(self.0.len().cmp(&others.len()))
+        })
+    }
     
     fn as_any(&self) -> &dyn Any {
         self
@@ -266,53 +640,196 @@ impl Val for Tuple {
     }
 }
 
+/// A matrix, whose elements are stored in row-major order.
+#[derive(Debug, Clone)]
+pub struct Matrix {
+    elements: Vec<Box<dyn Val>>,
+    rows: usize,
+    cols: usize
+}
+
+impl Matrix {
+    /// Creates a matrix from its rows, which must all be the same length.
+    pub fn from_rows(rows: Vec<Vec<Box<dyn Val>>>) -> Self {
+        let cols = rows.first().map_or(0, Vec::len);
+        let row_count = rows.len();
+
+        Self {
+            elements: rows.into_iter().flatten().collect(),
+            rows: row_count,
+            cols
+        }
+    }
+
+    /// Creates a matrix with the same dimensions, by applying `f` to each element, stopping at the first error.
+    pub fn try_map<F: FnMut(&dyn Val) -> error::Result<Box<dyn Val>>>(&self, mut f: F) -> error::Result<Self> {
+        Ok(Self {
+            elements: self.elements.iter().map(|element| f(element.as_ref())).collect::<error::Result<_>>()?,
+            rows: self.rows,
+            cols: self.cols
+        })
+    }
+
+    /// Creates a matrix with the same dimensions, by applying `f` to each pair of elements in the same position, stopping at the first error.
+    ///
+    /// Both matrices must have the same dimensions.
+    pub fn try_zip_map<F: FnMut(&dyn Val, &dyn Val) -> error::Result<Box<dyn Val>>>(&self, other: &Self, mut f: F) -> error::Result<Self> {
+        Ok(Self {
+            elements: self.elements.iter().zip(other.elements.iter()).map(|(a, b)| f(a.as_ref(), b.as_ref())).collect::<error::Result<_>>()?,
+            rows: self.rows,
+            cols: self.cols
+        })
+    }
+
+    /// Returns the element at the given row and column, counting from `0`.
+    pub fn get(&self, row: usize, col: usize) -> &dyn Val {
+        self.elements[row * self.cols + col].as_ref()
+    }
+
+    /// Returns the number of rows and columns.
+    pub fn dims(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+}
+
+impl Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+
+        for (i, element) in self.elements.iter().enumerate() {
+            if i == 0 {
+                write!(f, "{}", element)?;
+            } else if i % self.cols == 0 {
+                write!(f, "; {}", element)?;
+            } else {
+                write!(f, ", {}", element)?;
+            }
+        }
+
+        write!(f, "]")
+    }
+}
+
+impl Val for Matrix {
+    fn compare(&self, other: &dyn Val) -> bool {
+        if let Some(other_mat) = other.downcast_ref::<Matrix>() {
+            self.rows == other_mat.rows && self.cols == other_mat.cols && self.elements == other_mat.elements
+        } else {
+            false
+        }
+    }
+
+    fn hash_val(&self, mut state: &mut dyn Hasher) {
+        self.rows.hash(&mut state);
+        self.cols.hash(&mut state);
+        self.elements.hash(&mut state);
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Matrix"
+    }
+
+    fn is_mat(&self) -> bool {
+        true
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_boxed_any(&self) -> Box<dyn Any> {
+        Box::new(self.to_owned())
+    }
+}
+
+/// The argument types, codomain, and renamed body that decide whether two functions are the same.
+type CanonicalForm<'f> = (Vec<Rc<CanonSet>>, &'f Rc<CanonSet>, String);
+
 #[derive(Debug, Clone)]
 pub struct Func {
     env: Rc<RefCell<Env>>, // uses vec instead of hashmap because # of args is likely small and order by insertion is needed
     arg_names: Vec<String>,
     expr: Box<dyn Expr>,
-    codomain: Rc<CanonSet>
+    codomain: Rc<CanonSet>,
+    settings: Settings // of the interpreter that made the function, which its body runs with
 }
 
 impl Func {
-    pub fn new(env: Rc<RefCell<Env>>, arg_names: &[String], expr: Box<dyn Expr>, interned_set: &Rc<CanonSet>) -> Self {
+    pub fn new(env: Rc<RefCell<Env>>, arg_names: &[String], expr: Box<dyn Expr>, interned_set: &Rc<CanonSet>, settings: &Settings) -> Self {
         Self {
             env,
             arg_names: arg_names.to_owned(),
             expr,
-            codomain: Rc::clone(interned_set)
+            codomain: Rc::clone(interned_set),
+            settings: settings.clone()
         }
     }
 
-    pub fn from_func_expr(value: &expr::Func, parent: Rc<RefCell<Env>>) -> Self {
+    pub fn from_func_expr(value: &expr::Func, parent: Rc<RefCell<Env>>, settings: &Settings) -> error::Result<Self> {
         let mut arg_names = Vec::with_capacity(value.0.len());
         let mut env = Env::new(Some(Rc::clone(&parent)));
         
-        for sym in &value.0 {
-            env.insert_sym_type(sym.0.to_owned(), parent.borrow().get_set("Univ").unwrap());
+        for (sym, typeset) in value.0.iter().zip(&value.2) {
+            let typeset = match typeset {
+                // Inline arg types are found where the function is defined
+                Some(typeset) => {
+                    let typeset = Interpreter::with_settings(&parent, settings).execute_expr(typeset.as_ref())?;
+
+                    match typeset.downcast_ref::<Rc<CanonSet>>() {
+                        Some(set) => Rc::clone(set),
+                        None => return Err(RuntimeError::new(format!("'{typeset}' is not a set")).into())
+                    }
+                }
+                None => parent.borrow().get_set("Univ").unwrap()
+            };
+
+            env.insert_sym_type(sym.0.to_owned(), typeset);
             arg_names.push(sym.0.to_owned());
         }
 
-        Self {
+        Ok(Self {
             env: Rc::new(RefCell::new(env)),
             arg_names,
             expr: value.1.to_owned(),
-            codomain: parent.borrow().get_set("Univ").unwrap()
-        }
+            codomain: parent.borrow().get_set("Univ").unwrap(),
+            settings: settings.clone()
+        })
     }
 
-    pub fn clone_with_env(&self, new_env: Rc<RefCell<Env>>, ) -> Self {
-        Self {
-            env: new_env,
-            arg_names: self.arg_names.clone(),
-            expr: self.expr.clone(),
-            codomain: self.codomain.clone()
-        }
+    /// Returns what decides whether two functions are the same: their argument types, their codomain, and their body.
+    ///
+    /// The body has each argument renamed after its position, so that `x -> x` and `y -> y` are the same, and each other name
+    /// replaced by its value where the function was defined, so that `x -> x + a` differs wherever `a` does.
+    ///
+    /// This fails if the body has something that can't be part of a function, like an assignment.
+    fn canonical_form(&self) -> error::Result<CanonicalForm<'_>> {
+        let arg_types = self.arg_types();
+
+        // A name that isn't defined yet is left as it is
+        let args = self.arg_names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+        let mut expr = Interpreter::with_settings(&self.env, &self.settings)
+            .curry_expr(self.expr.as_ref(), &args)
+            .unwrap_or_else(|_| self.expr.to_owned());
+        let placeholders = (0..self.arity()).map(|i| format!("#{i}")).collect::<Vec<_>>();
+
+        Interpreter::substitute_symbols(&mut expr, &args, &placeholders)?;
+
+        Ok((arg_types, &self.codomain, expr.to_string()))
+    }
+
+    /// Checks if two functions are the same, which fails if either can't be compared.
+    pub fn try_compare(&self, other: &Func) -> error::Result<bool> {
+        Ok(self.arity() == other.arity() && self.canonical_form()? == other.canonical_form()?)
+    }
+
+    /// Checks that the function can be compared and hashed, which it must be to be put in a set.
+    pub fn check_comparable(&self) -> error::Result<()> {
+        self.canonical_form().map(|_| ())
     }
 
-    pub fn call(&self, args: &[Option<Box<dyn Val>>]) -> Box<dyn Val> {
+    pub fn call(&self, args: &[Option<Box<dyn Val>>]) -> error::Result<Box<dyn Val>> {
         if args.len() > self.arity() {
-            panic!("Too many arguments")
+            return Err(RuntimeError::new("Too many arguments").into())
         }
 
         let mut call_env = self.env.borrow().clone();
@@ -324,8 +841,8 @@ impl Func {
                 let arg_name = &self.arg_names[i];
                 
                 if let Some(SymStore::Type(typeset)) = self.env.borrow().get(arg_name) {
-                    if !typeset.contains(val) {
-                        panic!("Parameter '{arg_name}' belongs to '{typeset}' which doesn't contain '{val}'");
+                    if !typeset.try_contains(val.as_ref())? {
+                        return Err(RuntimeError::new(format!("Parameter '{arg_name}' belongs to '{typeset}' which doesn't contain '{val}'")).into());
                     }
                 } else {
                     unreachable!()
@@ -345,20 +862,27 @@ impl Func {
 
         let call_env = Rc::new(RefCell::new(call_env));
 
-        let mut interpreter = Interpreter::with_env(&call_env);
+        let mut interpreter = Interpreter::with_settings(&call_env, &self.settings);
 
-        if curried_args.len() > 0 {
-            return Box::new(
+        if !curried_args.is_empty() {
+            return Ok(Box::new(
                 Self {
                     env: Rc::clone(&call_env),
-                    expr: interpreter.curry_expr(&self.expr, &curried_args.iter().map(|s| s.as_str()).collect::<Vec<_>>()),
+                    expr: interpreter.curry_expr(self.expr.as_ref(), &curried_args.iter().map(|s| s.as_str()).collect::<Vec<_>>())?,
                     arg_names: curried_args,
-                    codomain: Rc::clone(&self.codomain)
+                    codomain: Rc::clone(&self.codomain),
+                    settings: self.settings.clone()
                 }
-            )
+            ))
         }
 
-        interpreter.execute_expr(&self.expr)
+        let result = interpreter.execute_expr(self.expr.as_ref())?;
+
+        if !self.codomain.try_contains(result.as_ref())? {
+            return Err(RuntimeError::new(format!("Result '{result}' is not in codomain '{}'", self.codomain)).into());
+        }
+
+        Ok(result)
     }
 
     pub fn is_defined(&self, name: &str) -> bool {
@@ -381,13 +905,30 @@ impl Func {
         &self.arg_names
     }
 
-    pub fn expr(&self) -> &Box<dyn Expr> {
-        &self.expr
+    pub fn expr(&self) -> &dyn Expr {
+        self.expr.as_ref()
+    }
+
+    /// Returns the set each argument is declared to be in (`Univ` if it was given no type).
+    pub fn arg_types(&self) -> Vec<Rc<CanonSet>> {
+        let env = self.env.borrow();
+
+        self.arg_names
+            .iter()
+            .filter_map(|name| match env.get(name) {
+                Some(SymStore::Type(typeset)) => Some(typeset),
+                _ => None
+            })
+            .collect()
     }
 
     pub fn codomain(&self) -> &Rc<CanonSet> {
         &self.codomain
     }
+
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
 }
 
 impl Display for Func {
@@ -399,7 +940,7 @@ impl Display for Func {
 
             for (i, a) in self.args().iter().enumerate() {
                 if i == self.arity() - 1 {
-                    s.push_str(&format!("{}", a));
+                    s.push_str(&a.to_string());
                 } else {
                     s.push_str(&format!("{}, ", a));
                 }
@@ -414,16 +955,20 @@ impl Display for Func {
     }
 }
 
+/// Functions with the same argument types, codomain, and body, up to the names of their arguments, hash the same.
+/// One that can't be compared only hashes its arity.
 impl Hash for Func {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        todo!()
+        self.arity().hash(state);
+        self.canonical_form().ok().hash(state);
     }
 }
 
 impl Val for Func {
+    /// Is [`Func::try_compare`], where a function that can't be compared is unequal to any other.
     fn compare(&self, other: &dyn Val) -> bool {
         if let Some(func @ Func { .. }) = other.downcast_ref() {
-            true // todo
+            self.try_compare(func).unwrap_or(false)
         } else {
             false
         }
@@ -433,6 +978,156 @@ impl Val for Func {
         self.hash(&mut state);
     }
 
+    fn type_name(&self) -> &'static str {
+        "Func"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_boxed_any(&self) -> Box<dyn Any> {
+        Box::new(self.to_owned())
+    }
+}
+
+/// The signature of the Rust function behind a [`NativeFunc`]. It is always given exactly `arity` arguments, or at least that many if it is variadic.
+pub type NativeFn = fn(&[Box<dyn Val>]) -> error::Result<Box<dyn Val>>;
+
+/// A function built into the language, implemented in Rust.
+///
+/// It is called with exactly `arity` arguments (or any number more, if it is variadic), and can't be curried.
+#[derive(Debug, Clone)]
+pub struct NativeFunc {
+    name: &'static str,
+    arity: usize,
+    variadic: bool,
+    func: NativeFn
+}
+
+impl NativeFunc {
+    pub fn new(name: &'static str, arity: usize, func: NativeFn) -> Self {
+        Self {
+            name,
+            arity,
+            variadic: false,
+            func
+        }
+    }
+
+    /// Creates a builtin that takes `arity` arguments or more.
+    pub fn variadic(name: &'static str, arity: usize, func: NativeFn) -> Self {
+        Self {
+            variadic: true,
+            ..Self::new(name, arity, func)
+        }
+    }
+
+    pub fn call(&self, args: &[Option<Box<dyn Val>>]) -> error::Result<Box<dyn Val>> {
+        if self.variadic && args.len() < self.arity {
+            return Err(RuntimeError::new(format!("Builtin function '{}' takes at least {} arguments, but {} were given", self.name, self.arity, args.len())).into())
+        } else if !self.variadic && args.len() != self.arity {
+            return Err(RuntimeError::new(format!("Builtin function '{}' takes {} arguments, but {} were given", self.name, self.arity, args.len())).into())
+        }
+
+        let Some(args) = args.iter().cloned().collect::<Option<Vec<_>>>() else {
+            return Err(RuntimeError::new(format!("Builtin function '{}' cannot be curried", self.name)).into())
+        };
+
+        (self.func)(&args)
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl Display for NativeFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<builtin {}>", self.name)
+    }
+}
+
+impl Val for NativeFunc {
+    fn compare(&self, other: &dyn Val) -> bool {
+        if let Some(other_native) = other.downcast_ref::<NativeFunc>() {
+            self.name == other_native.name
+        } else {
+            false
+        }
+    }
+
+    fn hash_val(&self, mut state: &mut dyn Hasher) {
+        self.name.hash(&mut state);
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Func"
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_boxed_any(&self) -> Box<dyn Any> {
+        Box::new(self.to_owned())
+    }
+}
+
+/// The symbols defined at the top level of an imported file, reached with `name.symbol`.
+#[derive(Debug, Clone)]
+pub struct Module {
+    path: String,
+    env: Rc<RefCell<Env>>
+}
+
+impl Module {
+    pub fn new(path: String, env: Rc<RefCell<Env>>) -> Self {
+        Self {
+            path,
+            env
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the value of a symbol the file itself defined, so builtins aren't reachable through it.
+    pub fn get(&self, name: &str) -> Option<Box<dyn Val>> {
+        let env = RefCell::borrow(&self.env);
+
+        match env.get(name) {
+            Some(SymStore::Value(value)) if env.contains_key(name) => Some(value),
+            _ => None
+        }
+    }
+}
+
+impl Display for Module {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<module \"{}\">", self.path)
+    }
+}
+
+impl Val for Module {
+    /// Modules are only equal if they are the same import.
+    fn compare(&self, other: &dyn Val) -> bool {
+        if let Some(other_module) = other.downcast_ref::<Module>() {
+            Rc::ptr_eq(&self.env, &other_module.env)
+        } else {
+            false
+        }
+    }
+
+    fn hash_val(&self, mut state: &mut dyn Hasher) {
+        Rc::as_ptr(&self.env).hash(&mut state);
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Module"
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -441,3 +1136,122 @@ impl Val for Func {
         Box::new(self.to_owned())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::ast::expr::Symbol;
+    use crate::builtins;
+    use crate::set::{FiniteSet, InfiniteSet};
+
+    fn one_of_each_type() -> Vec<Box<dyn Val>> {
+        let univ = Rc::new(CanonSet::Infinite(InfiniteSet::Univ));
+        let env = Rc::new(RefCell::new(Env::new(None)));
+        env.borrow_mut().insert_sym_type(String::from("x"), Rc::clone(&univ));
+
+        vec![
+            Box::new(BigInt::from(1)),
+            Box::new(BigRational::from(BigInt::from(1))),
+            Box::new(Complex::new(BigRational::from(BigInt::from(1)), BigRational::zero())),
+            Box::new(true),
+            Box::new(String::from("1")),
+            Box::new(Tuple(vec![Box::new(BigInt::from(1))])),
+            Box::new(Matrix::from_rows(vec![vec![Box::new(BigInt::from(1))]])),
+            Box::new(Rc::new(CanonSet::Finite(FiniteSet::new(HashSet::from([Box::new(BigInt::from(1)) as Box<dyn Val>]))))),
+            Box::new(Func::new(env, &[String::from("x")], Box::new(Symbol(String::from("x"))), &univ, &Settings::default())),
+            Box::new(NativeFunc::new("abs", 1, builtins::abs)),
+            Box::new(Unit),
+            Box::new(Cardinality::Countable)
+        ]
+    }
+
+    #[test]
+    fn coerce_pair_promotes_to_the_greater_kind() {
+        let values: [(Box<dyn Val>, NumKind); 4] = [
+            (Box::new(true), NumKind::Int),
+            (Box::new(BigInt::from(2)), NumKind::Int),
+            (Box::new(BigRational::new(BigInt::from(1), BigInt::from(2))), NumKind::Real),
+            (Box::new(Complex::new(BigRational::zero(), BigRational::from(BigInt::from(1)))), NumKind::Complex)
+        ];
+
+        for (left, left_kind) in &values {
+            for (right, right_kind) in &values {
+                let (l_num, r_num, kind) = coerce_pair(left.as_ref(), right.as_ref()).unwrap();
+
+                assert_eq!(kind, *left_kind.max(right_kind), "coercing '{left}' and '{right}'");
+                assert_eq!((l_num.kind(), r_num.kind()), (kind, kind));
+            }
+        }
+
+        assert!(coerce_pair(&BigInt::from(1), &String::from("1")).is_none());
+        assert!(coerce_pair(&Unit, &true).is_none());
+    }
+
+    #[test]
+    fn only_same_type_or_numbers_compare_equal() {
+        let values = one_of_each_type();
+
+        for (i, left) in values.iter().enumerate() {
+            for (j, right) in values.iter().enumerate() {
+                let expected = i == j || (left.is_num() && right.is_num());
+
+                assert_eq!(left.compare(right.as_ref()), expected, "comparing '{left}' with '{right}'");
+            }
+        }
+    }
+
+    #[test]
+    fn cached_display_matches_formatting() {
+        let long = BigInt::from(7).pow(2_000u32);
+        let values: [Box<dyn Val>; 3] = [
+            Box::new(long.clone()),
+            Box::new(BigRational::from(long.clone())),
+            Box::new(Complex::new(BigRational::from(long), BigRational::zero()))
+        ];
+
+        for value in &values {
+            assert!(value.is_costly_to_display());
+            assert_eq!(value.display(), format!("{value}"));
+            assert_eq!(value.display(), format!("{value}"), "displaying '{value}' from the cache");
+        }
+
+        assert!(!(&BigInt::from(7) as &dyn Val).is_costly_to_display());
+    }
+
+    #[test]
+    fn one_ordering_for_every_type() {
+        let rational = |numer: i64, denom: i64| Box::new(BigRational::new(BigInt::from(numer), BigInt::from(denom))) as Box<dyn Val>;
+        let string = |value: &str| Box::new(String::from(value)) as Box<dyn Val>;
+
+        // Each in order: bools, numbers by value whatever their representation, strings, then tuples element by element
+        let ordered: Vec<Box<dyn Val>> = vec![
+            Box::new(false),
+            Box::new(true),
+            Box::new(BigInt::from(-1)),
+            rational(1, 2),
+            Box::new(Complex::new(BigRational::from(BigInt::from(2)), BigRational::zero())),
+            Box::new(BigInt::from(3)),
+            string("a"),
+            string("ab"),
+            string("b"),
+            Box::new(Tuple(vec![Box::new(BigInt::from(0)), string("z")])),
+            Box::new(Tuple(vec![Box::new(BigInt::from(1)), string("a")])),
+            Box::new(Tuple(vec![Box::new(BigInt::from(1)), string("a"), string("a")]))
+        ];
+
+        for (i, left) in ordered.iter().enumerate() {
+            for (j, right) in ordered.iter().enumerate() {
+                assert_eq!(left.cmp_val(right.as_ref()), Some(i.cmp(&j)), "ordering '{left}' and '{right}'");
+                assert_eq!(left.total_cmp(right.as_ref()), i.cmp(&j), "ordering '{left}' and '{right}'");
+            }
+        }
+
+        // Complex numbers off the real axis have no order, even with each other
+        let i = Complex::new(BigRational::zero(), BigRational::from(BigInt::from(1)));
+
+        assert_eq!((&i as &dyn Val).cmp_val(&BigInt::from(1)), None);
+        assert_eq!((&i as &dyn Val).cmp_val(&i), None);
+    }
+}
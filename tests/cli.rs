@@ -0,0 +1,79 @@
+//! Runs the `math-lang` binary with files and flags, checking what it prints.
+
+use std::env;
+use std::fs;
+use std::process::Command;
+
+/// Runs `math-lang` with `args`, and returns what was printed.
+fn run_args(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_math-lang"))
+        .args(args)
+        .output()
+        .expect("failed to run math-lang");
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// Writes `source` to a file, runs it with `args`, and returns what was printed.
+fn run_file(name: &str, source: &str, args: &[&str]) -> String {
+    let path = env::temp_dir().join(name);
+    fs::write(&path, source).unwrap();
+
+    run_args(&[args, &[path.to_str().unwrap()]].concat())
+}
+
+#[test]
+fn file_prints_only_its_output() {
+    assert_eq!(run_file("math-lang-cli-output.math", "x = 1\nx + 1\n", &[]), "x = 1\n2\n");
+}
+
+#[test]
+fn ast_flag_prints_the_source_first() {
+    assert_eq!(run_file("math-lang-cli-ast.math", "x = 1\nx + 1\n", &["--ast"]), "x = 1\nx + 1\nx = 1\n2\n");
+}
+
+#[test]
+fn missing_file_is_reported_without_a_panic() {
+    let output = Command::new(env!("CARGO_BIN_EXE_math-lang"))
+        .arg("missing.math")
+        .output()
+        .expect("failed to run math-lang");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "error: cannot open 'missing.math': No such file\n");
+}
+
+#[test]
+fn lexer_error_is_reported_without_a_panic() {
+    let output = Command::new(env!("CARGO_BIN_EXE_math-lang"))
+        .args(["-e", "\"unclosed"])
+        .output()
+        .expect("failed to run math-lang");
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: unclosed string literal"));
+}
+
+#[test]
+fn strict_equality_flag() {
+    assert_eq!(run_args(&["-e", "2 == 2.0"]), "true\n");
+    assert_eq!(run_args(&["--strict-eq", "-e", "2 == 2.0"]), "false\n");
+}
+
+#[test]
+fn eval_prints_the_result() {
+    assert_eq!(run_args(&["-e", "2+2"]), "4\n");
+    assert_eq!(run_args(&["--eval", "x = 2\nx * 3"]), "x = 2\n6\n");
+}
+
+#[test]
+fn later_files_see_earlier_definitions() {
+    let first = env::temp_dir().join("math-lang-cli-first.math");
+    let second = env::temp_dir().join("math-lang-cli-second.math");
+    fs::write(&first, "f(x) = x + 1\n").unwrap();
+    fs::write(&second, "f(2)\n").unwrap();
+
+    assert_eq!(run_args(&[first.to_str().unwrap(), second.to_str().unwrap()]), "f = x -> x + 1\n3\n");
+}